@@ -0,0 +1,89 @@
+use chrono::{DateTime, Duration, Utc};
+use gloo_console::log;
+use gloo_storage::{LocalStorage, Storage};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    context::status::FetchAction,
+    utils::{fetch, FetchError},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cached<T> {
+    pub value: T,
+    pub fetched_at: DateTime<Utc>,
+    pub is_stale: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry<T> {
+    // A different url means different data, e.g. the location changed
+    url: String,
+    fetched_at: DateTime<Utc>,
+    value: T,
+}
+
+fn storage_key(key: &str) -> String {
+    format!("cache_{key}")
+}
+
+pub fn read<T>(key: &str, url: &str, ttl: Duration) -> Option<Cached<T>>
+where
+    T: DeserializeOwned,
+{
+    let entry = LocalStorage::get::<Entry<T>>(storage_key(key)).ok()?;
+
+    if entry.url != url {
+        return None;
+    }
+
+    Some(Cached {
+        is_stale: Utc::now() - entry.fetched_at > ttl,
+        fetched_at: entry.fetched_at,
+        value: entry.value,
+    })
+}
+
+// Always goes to the network, whatever comes back replaces the cached copy
+pub async fn fetch_cached<T>(key: &str, url: String) -> Result<Cached<T>, FetchError>
+where
+    T: Serialize + DeserializeOwned,
+{
+    let value = fetch::<T>(url.clone()).await?;
+    let entry = Entry {
+        url,
+        fetched_at: Utc::now(),
+        value,
+    };
+
+    // Running out of space shouldn't stop fresh data from showing up
+    if let Err(error) = LocalStorage::set(storage_key(key), &entry) {
+        log!(format!("Could not cache {key}: {error}"));
+    }
+
+    Ok(Cached {
+        value: entry.value,
+        fetched_at: entry.fetched_at,
+        is_stale: false,
+    })
+}
+
+// Hands over the cached copy straight away, then goes and gets a fresh one if it's too old
+pub async fn stale_while_revalidate<T, F>(key: &str, url: String, ttl: Duration, on_update: F)
+where
+    T: Serialize + DeserializeOwned + Clone,
+    F: Fn(FetchAction<Cached<T>>),
+{
+    let cached = read::<T>(key, &url, ttl);
+
+    if let Some(cached) = cached.clone() {
+        on_update(FetchAction::Finished(Ok(cached)));
+    }
+
+    if cached.is_some_and(|cached| !cached.is_stale) {
+        return;
+    }
+
+    on_update(FetchAction::Started);
+    on_update(FetchAction::Finished(fetch_cached(key, url).await));
+}
@@ -1,8 +1,17 @@
 pub mod bin;
+pub mod bin_calendar;
+pub mod bin_schedule_input;
+pub mod bus_stop_input;
 pub mod carousel;
 pub mod clock;
+pub mod departure_board;
 pub mod dim;
 pub mod location_input;
+pub mod place_weather;
+pub mod service_alerts;
+pub mod status;
+pub mod units_input;
 pub mod weather;
+pub mod weather_current;
 pub mod weather_daily;
 pub mod weather_hourly;
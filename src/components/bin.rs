@@ -1,47 +1,92 @@
-use chrono::prelude::*;
-use chrono::{DateTime, Local, Weekday};
+use chrono::{DateTime, Days, Local, NaiveDate, NaiveDateTime};
 use futures::StreamExt;
+use gloo_console::log;
+use gloo_storage::{LocalStorage, Storage};
 use std::time::Duration;
+use web_sys::{
+    wasm_bindgen::{closure::Closure, JsCast, JsValue},
+    AudioContext, OscillatorType,
+};
 use yew::platform::time::interval;
-use yew::{function_component, html, AttrValue, Component, Context, Html, Properties};
+use yew::{
+    classes, function_component, html, AttrValue, Callback, Component, Context, Html, Properties,
+};
 
-const REFRESH_HOURS: u64 = 1;
+use crate::{
+    context::clock::ClockContext,
+    schedule::{BinSchedule, ReminderSettings},
+};
 
-pub enum BinVariation {
-    Yellow,
-    None,
+// Often enough to catch the start of the reminder window
+const REFRESH_MINUTES: u64 = 1;
+
+const ACKNOWLEDGED_KEY: &str = "bin_reminder_acknowledged";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReminderState {
+    Idle,
+    // Bins for this collection need to go out and nobody has said they have
+    Due(NaiveDate),
+    Acknowledged(NaiveDate),
 }
 
-// Red bin is weekly
-// Yellow and Green alternate every week
-pub fn get_alternate_bin() -> BinVariation {
-    let known_yellow_bin_day = Local.with_ymd_and_hms(2024, 5, 13, 0, 0, 0).unwrap();
-    let diff = get_today() - known_yellow_bin_day;
+impl ReminderState {
+    pub fn evaluate(
+        settings: &ReminderSettings,
+        now: NaiveDateTime,
+        collection: NaiveDate,
+        acknowledged: Option<NaiveDate>,
+    ) -> Self {
+        let Some(starts) = collection.checked_sub_days(Days::new(settings.days_before as u64))
+        else {
+            return ReminderState::Idle;
+        };
+        let (starts, ends) = (
+            starts.and_time(settings.from),
+            collection.and_time(settings.until),
+        );
 
-    // I threw 💩 until it sorta worked
-    // Good luck
+        // Same day but `from` is later than `until`, e.g. 17:00 to 07:00, means the evening before
+        let starts = match starts >= ends {
+            true => starts - Days::new(1),
+            false => starts,
+        };
 
-    let wat = diff.num_days() % 14;
+        let is_in_window = now >= starts && now < ends;
 
-    if wat != 0 && wat <= 7 {
-        return BinVariation::None;
+        match (is_in_window, acknowledged == Some(collection)) {
+            (false, _) => ReminderState::Idle,
+            (true, false) => ReminderState::Due(collection),
+            (true, true) => ReminderState::Acknowledged(collection),
+        }
     }
-    return BinVariation::Yellow;
-}
-
-pub fn get_today() -> DateTime<Local> {
-    let current: DateTime<Local> = Local::now();
-    // let current = Local.with_ymd_and_hms(2024, 6, 4, 0, 0, 0).unwrap();
-    // let current = Local.with_ymd_and_hms(2024, 5, 28, 0, 0, 0).unwrap();
-    return current;
 }
 
 pub struct BinComponent {
     current_time: DateTime<Local>,
+    schedule: BinSchedule,
+    reminder: ReminderState,
 }
 
 pub enum BinComponentMsg {
     ClockTicked(DateTime<Local>),
+    Acknowledged,
+}
+
+impl BinComponent {
+    fn evaluate_reminder(&self) -> ReminderState {
+        let today = self.current_time.date_naive();
+
+        match self.schedule.next_collection(today) {
+            Some((date, _)) => ReminderState::evaluate(
+                &self.schedule.reminder,
+                self.current_time.naive_local(),
+                date,
+                LocalStorage::get::<NaiveDate>(ACKNOWLEDGED_KEY).ok(),
+            ),
+            None => ReminderState::Idle,
+        }
+    }
 }
 
 impl Component for BinComponent {
@@ -49,63 +94,278 @@ impl Component for BinComponent {
     type Properties = ();
 
     fn create(ctx: &Context<Self>) -> Self {
+        let (clock, _) = ctx
+            .link()
+            .context::<ClockContext>(Callback::noop())
+            .unwrap();
+
+        let time_clock = clock.clone();
         let time_steam =
-            interval(Duration::from_secs(60 * 60 * REFRESH_HOURS)).map(|_| get_today());
+            interval(Duration::from_secs(60 * REFRESH_MINUTES)).map(move |_| time_clock.now());
         ctx.link()
             .send_stream(time_steam.map(BinComponentMsg::ClockTicked));
 
-        Self {
-            current_time: get_today(),
-        }
+        let mut component = Self {
+            current_time: clock.now(),
+            schedule: BinSchedule::load(),
+            reminder: ReminderState::Idle,
+        };
+        component.reminder = component.evaluate_reminder();
+        component
     }
 
     fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
             BinComponentMsg::ClockTicked(current_time) => {
                 self.current_time = current_time;
+                // Pick up rota changes without a reload
+                self.schedule = BinSchedule::load();
+
+                let reminder = self.evaluate_reminder();
+                let became_due = matches!(reminder, ReminderState::Due(_))
+                    && !matches!(self.reminder, ReminderState::Due(_));
+
+                if became_due && self.schedule.reminder.chime {
+                    // Browsers can refuse to play before anyone has touched the page
+                    let _ = play_chime();
+                }
+                self.reminder = reminder;
+            }
+            BinComponentMsg::Acknowledged => {
+                if let ReminderState::Due(date) = self.reminder {
+                    // Still hide it if storage is full, it'll just come back after a reload
+                    if let Err(error) = LocalStorage::set(ACKNOWLEDGED_KEY, date) {
+                        log!(format!(
+                            "Could not save bin reminder acknowledgement: {error}"
+                        ));
+                    }
+                    self.reminder = ReminderState::Acknowledged(date);
+                }
             }
         }
         true
     }
 
-    fn view(&self, _ctx: &Context<Self>) -> Html {
-        html! {
-            <div class="d-flex align-items-center">
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let today = self.current_time.date_naive();
 
-                <BinSVG color="red" />
-                <BinSVG color="green"  />
+        let Some((date, streams)) = self.schedule.next_collection(today) else {
+            return html! {};
+        };
 
-                {
-                    match get_alternate_bin() {
-                        BinVariation::Yellow => html! { <BinSVG color="orange" /> },
-                        BinVariation::None => html! { }
-                    }
+        // Public holiday moved it off the usual day
+        let is_shifted = streams
+            .iter()
+            .any(|stream| stream.is_shifted_on(date, &self.schedule.holidays));
+
+        let is_due = matches!(self.reminder, ReminderState::Due(_));
+
+        let bins = streams
+            .iter()
+            .map(|stream| {
+                html! {
+                    <BinSVG color={stream.color.clone()} label={stream.label.clone()} pulse={is_due} />
                 }
+            })
+            .collect::<Html>();
+
+        let acknowledge_onclick = ctx.link().callback(|_| BinComponentMsg::Acknowledged);
+
+        html! {
+            <div class="d-flex align-items-center">
+
+                {bins.clone()}
 
                 <div class="fs-1 fw-bold text-white">
-                    if self.current_time.weekday() == Weekday::Mon {
-                        {"BIN DAY TODAY!!"}
-                    } else {
-                        {format!("{} days", 7 -  self.current_time.weekday().num_days_from_monday())}
+                    {countdown(today, date)}
+                    if is_shifted {
+                        {format!(" ({})", date.format("%a"))}
                     }
                 </div>
+
+                if is_due {
+                    <div
+                        class="position-fixed top-0 start-0 w-100 h-100 d-flex flex-column align-items-center justify-content-center bg-danger text-white"
+                        style="z-index: 2000;"
+                        onclick={acknowledge_onclick}
+                    >
+                        <div class="display-1 fw-bold">{"PUT THE BINS OUT"}</div>
+                        <div class="d-flex">{bins}</div>
+                        <div class="fs-3">{"Tap once they're out"}</div>
+                    </div>
+                }
             </div>
         }
     }
 }
 
+pub fn countdown(today: NaiveDate, collection: NaiveDate) -> String {
+    match (collection - today).num_days() {
+        0 => "BIN DAY TODAY!!".to_string(),
+        days => format!("{days} days"),
+    }
+}
+
+fn play_chime() -> Result<(), JsValue> {
+    let audio = AudioContext::new()?;
+    let oscillator = audio.create_oscillator()?;
+    let gain = audio.create_gain()?;
+    let now = audio.current_time();
+
+    oscillator.set_type(OscillatorType::Sine);
+    oscillator.frequency().set_value(880.0);
+    gain.gain().set_value_at_time(0.3, now)?;
+    gain.gain()
+        .exponential_ramp_to_value_at_time(0.001, now + 1.5)?;
+
+    oscillator.connect_with_audio_node(&gain)?;
+    gain.connect_with_audio_node(&audio.destination())?;
+
+    // Browsers only allow a handful of these open at once
+    let close = Closure::once_into_js(move || {
+        let _ = audio.close();
+    });
+    oscillator.set_onended(Some(close.unchecked_ref()));
+
+    oscillator.start()?;
+    oscillator.stop_with_when(now + 1.5)?;
+
+    Ok(())
+}
+
 #[derive(Properties, PartialEq)]
 pub struct BinSVGProps {
     pub color: AttrValue,
+    #[prop_or_default]
+    pub label: AttrValue,
+    #[prop_or_default]
+    pub pulse: bool,
 }
 
 #[function_component]
-fn BinSVG(&BinSVGProps { ref color }: &BinSVGProps) -> Html {
+fn BinSVG(
+    BinSVGProps {
+        color,
+        label,
+        pulse,
+    }: &BinSVGProps,
+) -> Html {
     html! {
-        <svg xmlns="http://www.w3.org/2000/svg" fill="white" height="80px" width="74px" viewBox="16 0 268 300">
+        <svg class={classes!(pulse.then_some("pulse"))} xmlns="http://www.w3.org/2000/svg" fill="white" height="80px" width="74px" viewBox="16 0 268 300">
+            <title>{label}</title>
             <path style={format!("fill:{}", color)} d="M221.74,94.57L207.98,259.6c-0.79,9.51-8.88,16.95-18.42,16.95h-82.12 c-9.53,0-17.63-7.44-18.42-16.95L75.27,94.57H221.74z"/>
             <path style={format!("fill:{}", color)} d="M239.49,65.69v8.43H57.51v-8.43c0-6.32,5.14-11.47,11.47-11.47h159.05 C234.35,54.22,239.49,59.37,239.49,65.69z"/>
             <path d="M259.94,65.69v18.66c0,5.64-4.58,10.22-10.22,10.22h-7.46l-13.9,166.73c-1.67,20.02-18.71,35.7-38.8,35.7h-82.12 c-20.08,0-37.13-15.68-38.8-35.7L54.75,94.57h-7.46c-5.65,0-10.23-4.58-10.23-10.22V65.69c0-17.6,14.32-31.91,31.92-31.91h35.56    v-4.73C104.54,13.03,117.57,0,133.59,0h29.82c16.02,0,29.06,13.03,29.06,29.05v4.73h35.56C245.63,33.78,259.94,48.09,259.94,65.69    z M239.49,74.12v-8.43c0-6.32-5.14-11.47-11.46-11.47H68.98c-6.33,0-11.47,5.15-11.47,11.47v8.43H239.49z M207.98,259.6    l13.76-165.03H75.27L89.02,259.6c0.79,9.51,8.89,16.95,18.42,16.95h82.12C199.1,276.55,207.19,269.11,207.98,259.6z M172.02,33.78    v-4.73c0-4.74-3.86-8.6-8.61-8.6h-29.82c-4.74,0-8.6,3.86-8.6,8.6v4.73H172.02z"/>
         </svg>
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveTime;
+
+    use super::*;
+
+    // The default rota is collected on Mondays, the 20th of May 2024 had no holidays that week
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 5, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    fn countdown_at(now: NaiveDateTime) -> String {
+        let today = now.date();
+        let (collection, _) = BinSchedule::default().next_collection(today).unwrap();
+        countdown(today, collection)
+    }
+
+    fn reminder_at(now: NaiveDateTime, acknowledged: Option<NaiveDate>) -> ReminderState {
+        let schedule = BinSchedule::default();
+        let (collection, _) = schedule.next_collection(now.date()).unwrap();
+        ReminderState::evaluate(&schedule.reminder, now, collection, acknowledged)
+    }
+
+    fn monday() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 5, 20).unwrap()
+    }
+
+    #[test]
+    fn counts_down_the_week() {
+        assert_eq!(countdown_at(at(14, 12, 0)), "6 days");
+        assert_eq!(countdown_at(at(18, 12, 0)), "2 days");
+        assert_eq!(countdown_at(at(19, 12, 0)), "1 days");
+    }
+
+    #[test]
+    fn rolls_over_at_midnight() {
+        assert_eq!(countdown_at(at(19, 23, 59)), "1 days");
+        assert_eq!(countdown_at(at(20, 0, 0)), "BIN DAY TODAY!!");
+        assert_eq!(countdown_at(at(20, 23, 59)), "BIN DAY TODAY!!");
+        assert_eq!(countdown_at(at(21, 0, 0)), "6 days");
+    }
+
+    #[test]
+    fn reminds_from_the_evening_before() {
+        assert_eq!(reminder_at(at(19, 16, 59), None), ReminderState::Idle);
+        assert_eq!(
+            reminder_at(at(19, 17, 0), None),
+            ReminderState::Due(monday())
+        );
+        assert_eq!(
+            reminder_at(at(20, 0, 0), None),
+            ReminderState::Due(monday())
+        );
+        assert_eq!(
+            reminder_at(at(20, 6, 59), None),
+            ReminderState::Due(monday())
+        );
+        assert_eq!(reminder_at(at(20, 7, 0), None), ReminderState::Idle);
+    }
+
+    #[test]
+    fn reminds_on_the_morning_with_no_days_before() {
+        let settings = ReminderSettings {
+            days_before: 0,
+            from: NaiveTime::from_hms_opt(5, 0, 0).unwrap(),
+            ..Default::default()
+        };
+        let reminder =
+            |day, hour| ReminderState::evaluate(&settings, at(day, hour, 0), monday(), None);
+
+        assert_eq!(reminder(19, 18), ReminderState::Idle);
+        assert_eq!(reminder(20, 4), ReminderState::Idle);
+        assert_eq!(reminder(20, 5), ReminderState::Due(monday()));
+        assert_eq!(reminder(20, 7), ReminderState::Idle);
+    }
+
+    #[test]
+    fn reminds_the_evening_before_with_no_days_before() {
+        let settings = ReminderSettings {
+            days_before: 0,
+            ..Default::default()
+        };
+        let reminder =
+            |day, hour| ReminderState::evaluate(&settings, at(day, hour, 0), monday(), None);
+
+        assert_eq!(reminder(19, 16), ReminderState::Idle);
+        assert_eq!(reminder(19, 17), ReminderState::Due(monday()));
+        assert_eq!(reminder(20, 6), ReminderState::Due(monday()));
+        assert_eq!(reminder(20, 7), ReminderState::Idle);
+    }
+
+    #[test]
+    fn stays_quiet_once_acknowledged() {
+        assert_eq!(
+            reminder_at(at(19, 18, 0), Some(monday())),
+            ReminderState::Acknowledged(monday())
+        );
+
+        // Last week's doesn't count
+        let last_week = monday() - Days::new(7);
+        assert_eq!(
+            reminder_at(at(19, 18, 0), Some(last_week)),
+            ReminderState::Due(monday())
+        );
+    }
+}
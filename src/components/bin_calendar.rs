@@ -0,0 +1,84 @@
+use chrono::{Datelike, Weekday};
+use yew::{function_component, html, use_context, use_state, Html};
+use yew_hooks::use_interval;
+
+use crate::{context::clock::ClockContext, schedule::BinSchedule};
+
+const WEEKS: usize = 8;
+const REFRESH_MILLIS: u32 = 3_600_000; // Every hour
+
+#[function_component]
+pub fn BinCalendar() -> Html {
+    let clock = use_context::<ClockContext>().unwrap();
+    let schedule = use_state(BinSchedule::load);
+    let today = use_state(|| clock.now().date_naive());
+
+    {
+        let schedule = schedule.clone();
+        let today = today.clone();
+        use_interval(
+            move || {
+                schedule.set(BinSchedule::load());
+                today.set(clock.now().date_naive());
+            },
+            REFRESH_MILLIS,
+        );
+    }
+
+    let first_day = today.week(Weekday::Mon).first_day();
+    let days = first_day.iter_days().take(WEEKS * 7).collect::<Vec<_>>();
+
+    html! {
+        <table class="table table-sm table-borderless text-white text-center mb-0">
+            <thead>
+                <tr>
+                    {
+                        ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"]
+                            .iter()
+                            .map(|day| html! { <th>{day}</th> })
+                            .collect::<Html>()
+                    }
+                </tr>
+            </thead>
+            <tbody>
+            {
+                days.chunks(7).map(|week| html! {
+                    <tr>
+                    {
+                        week.iter().map(|date| {
+                            let streams = schedule.collected_on(*date);
+                            let is_today = *date == *today;
+
+                            // Only label the month where it changes, keeps the grid readable
+                            let label = match date.day() == 1 || *date == first_day {
+                                true => format!("{}", date.format("%d %b")),
+                                false => format!("{}", date.format("%d")),
+                            };
+
+                            html! {
+                                <td class={if is_today { "border border-white" } else { "" }}>
+                                    <div class={if *date < *today { "opacity-50" } else { "" }}>
+                                        {label}
+                                    </div>
+                                    <div class="d-flex justify-content-center gap-1" style="min-height: 14px;">
+                                    {
+                                        streams.iter().map(|stream| html! {
+                                            <span
+                                                title={stream.label.clone()}
+                                                class="rounded-circle d-inline-block"
+                                                style={format!("width: 14px; height: 14px; background-color: {}", stream.color)}
+                                            ></span>
+                                        }).collect::<Html>()
+                                    }
+                                    </div>
+                                </td>
+                            }
+                        }).collect::<Html>()
+                    }
+                    </tr>
+                }).collect::<Html>()
+            }
+            </tbody>
+        </table>
+    }
+}
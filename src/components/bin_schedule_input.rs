@@ -0,0 +1,259 @@
+use chrono::{NaiveDate, NaiveTime};
+use web_sys::{wasm_bindgen::JsCast, EventTarget, FormData, HtmlFormElement};
+use yew::{
+    function_component, html, platform::spawn_local, use_context, use_state, Callback, Html,
+    SubmitEvent, UseStateHandle,
+};
+
+use crate::{
+    context::clock::ClockContext,
+    schedule::{holidays::Region, ics, BinSchedule},
+    utils::fetch_text,
+};
+
+const UPCOMING_HOLIDAY_DAYS: usize = 60;
+
+#[function_component]
+pub fn BinScheduleInput() -> Html {
+    let clock = use_context::<ClockContext>().unwrap();
+    let schedule = use_state(BinSchedule::load);
+    let status = use_state(String::new);
+
+    let form_onsubmit = {
+        let schedule = schedule.clone();
+        let status = status.clone();
+
+        Callback::from(move |event: SubmitEvent| {
+            event.prevent_default();
+
+            let target: Option<EventTarget> = event.target();
+            let form = target.and_then(|t| t.dyn_into::<HtmlFormElement>().ok());
+
+            if let Some(form) = form {
+                let form_data = FormData::new_with_form(&form).unwrap();
+                let url = form_data.get("url").as_string().unwrap_or_default();
+                let pasted = form_data.get("ics").as_string().unwrap_or_default();
+
+                let schedule = schedule.clone();
+                let status = status.clone();
+
+                if url.trim().is_empty() {
+                    import(&pasted, schedule, status);
+                } else {
+                    spawn_local(async move {
+                        match fetch_text(url.trim().to_string()).await {
+                            Ok(calendar) => import(&calendar, schedule, status),
+                            Err(error) => status.set(format!("Could not load calendar: {error}")),
+                        }
+                    });
+                }
+            }
+        })
+    };
+
+    let reset_onclick = {
+        let schedule = schedule.clone();
+        let status = status.clone();
+
+        Callback::from(move |_| {
+            let default = BinSchedule::default();
+            default.save();
+            schedule.set(default);
+            status.set("Back to the default rota".to_string());
+        })
+    };
+
+    let holiday_onsubmit = {
+        let schedule = schedule.clone();
+
+        Callback::from(move |event: SubmitEvent| {
+            event.prevent_default();
+
+            let target: Option<EventTarget> = event.target();
+            let form = target.and_then(|t| t.dyn_into::<HtmlFormElement>().ok());
+
+            if let Some(form) = form {
+                let form_data = FormData::new_with_form(&form).unwrap();
+                let date = form_data.get("holiday").as_string().unwrap_or_default();
+
+                if let Ok(date) = NaiveDate::parse_from_str(&date, "%Y-%m-%d") {
+                    let mut updated = (*schedule).clone();
+                    updated.holidays.extra.push(date);
+                    updated.holidays.extra.sort();
+                    updated.holidays.extra.dedup();
+                    updated.save();
+                    schedule.set(updated);
+                }
+            }
+        })
+    };
+
+    let region_onclick = {
+        let schedule = schedule.clone();
+
+        Callback::from(move |_| {
+            let mut updated = (*schedule).clone();
+            updated.holidays.region = match updated.holidays.region {
+                Some(_) => None,
+                None => Some(Region::Nsw),
+            };
+            updated.save();
+            schedule.set(updated);
+        })
+    };
+
+    let reminder_onsubmit = {
+        let schedule = schedule.clone();
+
+        Callback::from(move |event: SubmitEvent| {
+            event.prevent_default();
+
+            let target: Option<EventTarget> = event.target();
+            let form = target.and_then(|t| t.dyn_into::<HtmlFormElement>().ok());
+
+            if let Some(form) = form {
+                let form_data = FormData::new_with_form(&form).unwrap();
+                let days_before = form_data.get("days_before").as_string().unwrap_or_default();
+                let from = form_data.get("from").as_string().unwrap_or_default();
+
+                let mut updated = (*schedule).clone();
+                if let Ok(days_before) = days_before.parse::<u32>() {
+                    updated.reminder.days_before = days_before;
+                }
+                if let Ok(from) = NaiveTime::parse_from_str(&from, "%H:%M") {
+                    updated.reminder.from = from;
+                }
+                updated.save();
+                schedule.set(updated);
+            }
+        })
+    };
+
+    let chime_onclick = {
+        let schedule = schedule.clone();
+
+        Callback::from(move |_| {
+            let mut updated = (*schedule).clone();
+            updated.reminder.chime = !updated.reminder.chime;
+            updated.save();
+            schedule.set(updated);
+        })
+    };
+
+    let today = clock.now().date_naive();
+
+    let upcoming_holidays = today
+        .iter_days()
+        .take(UPCOMING_HOLIDAY_DAYS)
+        .filter(|date| schedule.holidays.is_holiday(*date))
+        .collect::<Vec<_>>();
+
+    html! {
+        <div>
+            <div class="d-flex gap-5">
+                <div>
+                    {
+                        schedule.streams.iter().map(|stream| {
+                            let next = stream
+                                .next_collection(today, &schedule.holidays)
+                                .map(|date| format!("{}", date.format("%a %d %b")))
+                                .unwrap_or("-".to_string());
+
+                            html! {
+                                <div>
+                                    <span style={format!("color:{}", stream.color)}>{"● "}</span>
+                                    {&stream.label}{": "}{next}
+                                </div>
+                            }
+                        }).collect::<Html>()
+                    }
+                </div>
+
+                <button onclick={reset_onclick}>
+                    {"Reset"}
+                </button>
+            </div>
+
+            <form class="d-flex flex-column gap-3 mt-2" onsubmit={ form_onsubmit }>
+
+                <div class="input-group">
+                    <div class="input-group-prepend">
+                        <span class="input-group-text">{"Calendar URL"}</span>
+                    </div>
+                    <input type="text" name="url" id="url" class="form-control" placeholder="/collections.ics" />
+                </div>
+
+                <textarea name="ics" class="form-control" rows="3" placeholder="Or paste the .ics file here"></textarea>
+
+                <button class="btn btn-primary">{"Import"}</button>
+            </form>
+
+            <div>{(*status).clone()}</div>
+
+            <div class="d-flex gap-3 mt-3 align-items-center">
+                <button onclick={region_onclick}>
+                    {
+                        match schedule.holidays.region {
+                            Some(_) => "NSW holidays: on",
+                            None => "NSW holidays: off",
+                        }
+                    }
+                </button>
+
+                <form class="d-flex gap-2" onsubmit={ holiday_onsubmit }>
+                    <input type="date" name="holiday" class="form-control" required={true} />
+                    <button class="btn btn-primary text-nowrap">{"Add holiday"}</button>
+                </form>
+            </div>
+
+            <div class="d-flex gap-3 mt-3 align-items-center">
+                <form class="d-flex gap-2 align-items-center" onsubmit={ reminder_onsubmit }>
+                    <span class="text-nowrap">{"Remind"}</span>
+                    <input type="number" name="days_before" class="form-control" min="0" value={schedule.reminder.days_before.to_string()} />
+                    <span class="text-nowrap">{"days before from"}</span>
+                    <input type="time" name="from" class="form-control" value={format!("{}", schedule.reminder.from.format("%H:%M"))} />
+                    <button class="btn btn-primary">{"Save"}</button>
+                </form>
+
+                <button onclick={chime_onclick}>
+                    {
+                        match schedule.reminder.chime {
+                            true => "Chime: on",
+                            false => "Chime: off",
+                        }
+                    }
+                </button>
+            </div>
+
+            <div>
+                {"Upcoming holidays: "}
+                {
+                    upcoming_holidays
+                        .iter()
+                        .map(|date| format!("{}", date.format("%a %d %b")))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                }
+            </div>
+        </div>
+    }
+}
+
+fn import(calendar: &str, schedule: UseStateHandle<BinSchedule>, status: UseStateHandle<String>) {
+    let events = ics::parse(calendar);
+
+    if events.is_empty() {
+        status.set("No collections found in that calendar".to_string());
+        return;
+    }
+
+    let mut updated = (*schedule).clone();
+    updated.import(events);
+    updated.save();
+
+    status.set(format!(
+        "Imported {} bins, the header catches up within the hour",
+        updated.streams.len()
+    ));
+    schedule.set(updated);
+}
@@ -0,0 +1,349 @@
+use std::rc::Rc;
+use web_sys::{wasm_bindgen::JsCast, EventTarget, FormData, HtmlFormElement, HtmlInputElement};
+use yew::{
+    function_component, html, platform::spawn_local, use_context, use_state, Callback, Event, Html,
+    SubmitEvent, TargetCast,
+};
+
+use crate::context::bussin::{
+    gtfs::{self, Timetable},
+    save_stops,
+    source::SourceConfig,
+    BusAction, BusContext, BusStop, TransportMode, MAX_STOPS,
+};
+
+#[function_component]
+pub fn BusStopInput() -> Html {
+    let bus_ctx = use_context::<BusContext>().unwrap();
+    let results = use_state(Vec::<BusStop>::new);
+    let status = use_state(String::new);
+
+    let search_onsubmit = {
+        let bus_ctx = bus_ctx.clone();
+        let results = results.clone();
+        let status = status.clone();
+
+        Callback::from(move |event: SubmitEvent| {
+            event.prevent_default();
+
+            let target: Option<EventTarget> = event.target();
+            let form = target.and_then(|t| t.dyn_into::<HtmlFormElement>().ok());
+
+            if let Some(form) = form {
+                let form_data = FormData::new_with_form(&form).unwrap();
+                let query = form_data.get("query").as_string().unwrap_or_default();
+
+                let results = results.clone();
+                let status = status.clone();
+                status.set("Searching...".to_string());
+
+                let source = bus_ctx.source.source(bus_ctx.timetable.clone());
+                spawn_local(async move {
+                    match source.search_stops(&query).await {
+                        Ok(stops) => {
+                            status.set(format!("{} stops found", stops.len()));
+                            results.set(stops);
+                        }
+                        Err(error) => status.set(format!("Could not search stops: {error}")),
+                    }
+                });
+            }
+        })
+    };
+
+    let timetable_status = use_state(String::new);
+
+    let timetable_onsubmit = {
+        let bus_ctx = bus_ctx.clone();
+        let timetable_status = timetable_status.clone();
+
+        Callback::from(move |event: SubmitEvent| {
+            event.prevent_default();
+
+            let target: Option<EventTarget> = event.target();
+            let form = target.and_then(|t| t.dyn_into::<HtmlFormElement>().ok());
+
+            if let Some(form) = form {
+                let form_data = FormData::new_with_form(&form).unwrap();
+                let url = form_data.get("url").as_string().unwrap_or_default();
+
+                let bus_ctx = bus_ctx.clone();
+                let stops = bus_ctx.stops.clone();
+                let timetable_status = timetable_status.clone();
+                timetable_status.set("Loading timetable...".to_string());
+
+                spawn_local(async move {
+                    match gtfs::import(&url, &stops).await {
+                        Ok(imported) => match imported.save().await {
+                            Ok(()) => {
+                                timetable_status.set(String::new());
+                                bus_ctx.dispatch(BusAction::SetTimetable(Some(Rc::new(imported))));
+                            }
+                            Err(error) => {
+                                timetable_status.set(format!("Could not save timetable: {error}"))
+                            }
+                        },
+                        Err(error) => {
+                            timetable_status.set(format!("Could not load timetable: {error}"))
+                        }
+                    }
+                });
+            }
+        })
+    };
+
+    let timetable_clear = {
+        let bus_ctx = bus_ctx.clone();
+        let timetable_status = timetable_status.clone();
+        Callback::from(move |_| {
+            let bus_ctx = bus_ctx.clone();
+            let timetable_status = timetable_status.clone();
+            spawn_local(async move {
+                match Timetable::clear().await {
+                    Ok(()) => bus_ctx.dispatch(BusAction::SetTimetable(None)),
+                    Err(error) => {
+                        timetable_status.set(format!("Could not clear timetable: {error}"))
+                    }
+                }
+            });
+        })
+    };
+
+    let source_onsubmit = {
+        let bus_ctx = bus_ctx.clone();
+
+        Callback::from(move |event: SubmitEvent| {
+            event.prevent_default();
+
+            let target: Option<EventTarget> = event.target();
+            let form = target.and_then(|t| t.dyn_into::<HtmlFormElement>().ok());
+
+            if let Some(form) = form {
+                let form_data = FormData::new_with_form(&form).unwrap();
+                let field = |name| form_data.get(name).as_string().unwrap_or_default();
+
+                let source = match field("source").as_str() {
+                    "gtfs_realtime" => SourceConfig::GtfsRealtime {
+                        trip_updates_url: field("trip_updates_url").trim().to_string(),
+                        alerts_url: field("alerts_url").trim().to_string(),
+                        api_key: field("api_key").trim().to_string(),
+                    },
+                    "open_trip_planner" => SourceConfig::OpenTripPlanner {
+                        graphql_url: field("graphql_url").trim().to_string(),
+                    },
+                    _ => SourceConfig::TransportNsw,
+                };
+
+                source.save();
+                bus_ctx.dispatch(BusAction::SetSource(source));
+            }
+        })
+    };
+
+    let (trip_updates_url, alerts_url, api_key) = match &bus_ctx.source {
+        SourceConfig::GtfsRealtime {
+            trip_updates_url,
+            alerts_url,
+            api_key,
+        } => (
+            trip_updates_url.clone(),
+            alerts_url.clone(),
+            api_key.clone(),
+        ),
+        _ => Default::default(),
+    };
+
+    let graphql_url = match &bus_ctx.source {
+        SourceConfig::OpenTripPlanner { graphql_url } => graphql_url.clone(),
+        _ => String::new(),
+    };
+
+    // Every change is saved straight away and handed to the provider
+    let update_stops = {
+        let bus_ctx = bus_ctx.clone();
+        move |stops: Vec<BusStop>| {
+            save_stops(&stops);
+            bus_ctx.dispatch(BusAction::SetStops(stops));
+        }
+    };
+
+    let stops = bus_ctx.stops.clone();
+    let is_full = stops.len() >= MAX_STOPS;
+
+    html! {
+        <div class="d-flex gap-5">
+            <div class="flex-fill">
+                {format!("Saved stops ({}/{MAX_STOPS})", stops.len())}
+                {
+                    stops.iter().enumerate().map(|(i, stop)| {
+                        let move_up = {
+                            let update_stops = update_stops.clone();
+                            let stops = stops.clone();
+                            Callback::from(move |_| {
+                                let mut stops = stops.clone();
+                                if i > 0 {
+                                    stops.swap(i, i - 1);
+                                    update_stops(stops);
+                                }
+                            })
+                        };
+
+                        let move_down = {
+                            let update_stops = update_stops.clone();
+                            let stops = stops.clone();
+                            Callback::from(move |_| {
+                                let mut stops = stops.clone();
+                                if i + 1 < stops.len() {
+                                    stops.swap(i, i + 1);
+                                    update_stops(stops);
+                                }
+                            })
+                        };
+
+                        let remove = {
+                            let update_stops = update_stops.clone();
+                            let stops = stops.clone();
+                            Callback::from(move |_| {
+                                let mut stops = stops.clone();
+                                stops.remove(i);
+                                update_stops(stops);
+                            })
+                        };
+
+                        let set_walking_minutes = {
+                            let update_stops = update_stops.clone();
+                            let stops = stops.clone();
+                            Callback::from(move |event: Event| {
+                                let input: HtmlInputElement = event.target_unchecked_into();
+                                if let Ok(minutes) = input.value().parse::<u32>() {
+                                    let mut stops = stops.clone();
+                                    stops[i].walking_minutes = minutes;
+                                    update_stops(stops);
+                                }
+                            })
+                        };
+
+                        let mode_toggles = TransportMode::ALL.iter().map(|mode| {
+                            let is_on = stop.modes.contains(mode);
+
+                            let toggle = {
+                                let update_stops = update_stops.clone();
+                                let stops = stops.clone();
+                                let mode = *mode;
+                                Callback::from(move |_| {
+                                    let mut stops = stops.clone();
+                                    let modes = &mut stops[i].modes;
+                                    match modes.contains(&mode) {
+                                        true => modes.retain(|m| *m != mode),
+                                        false => modes.push(mode),
+                                    }
+                                    update_stops(stops);
+                                })
+                            };
+
+                            html! {
+                                <button
+                                    class={if is_on { "" } else { "opacity-25" }}
+                                    title={mode.name()}
+                                    onclick={toggle}
+                                >
+                                    {mode.icon()}
+                                </button>
+                            }
+                        }).collect::<Html>();
+
+                        html! {
+                            <div class="mt-1">
+                                <div class="d-flex gap-1 align-items-center">
+                                    <span class="flex-fill">{&stop.name}</span>
+                                    <button onclick={move_up}>{"↑"}</button>
+                                    <button onclick={move_down}>{"↓"}</button>
+                                    <button onclick={remove}>{"✕"}</button>
+                                </div>
+                                <div class="d-flex gap-1 align-items-center">
+                                    {mode_toggles}
+                                    <input
+                                        type="number"
+                                        min="0"
+                                        class="form-control form-control-sm ms-2"
+                                        style="width: 5em;"
+                                        title="Walking time in minutes"
+                                        value={stop.walking_minutes.to_string()}
+                                        onchange={set_walking_minutes}
+                                    />
+                                    {"min walk"}
+                                </div>
+                            </div>
+                        }
+                    }).collect::<Html>()
+                }
+
+                <div class="mt-3">{"Offline timetable"}</div>
+                <form class="d-flex gap-2" onsubmit={ timetable_onsubmit }>
+                    <input type="text" name="url" class="form-control" placeholder="Unzipped GTFS folder, e.g. /gtfs" required={true} />
+                    <button class="btn btn-primary">{"Import"}</button>
+                </form>
+                <div>
+                    {(*timetable_status).clone()}
+                    if let Some(timetable) = &bus_ctx.timetable {
+                        {format!("{} scheduled departures for {} stops, import again after changing stops ", timetable.call_count(), timetable.stops.len())}
+                        <button onclick={timetable_clear}>{"Clear"}</button>
+                    }
+                </div>
+
+                <div class="mt-3">{"Departures from"}</div>
+                <form class="d-flex flex-wrap gap-2" onsubmit={ source_onsubmit }>
+                    <select name="source" class="form-select">
+                        <option value="transport_nsw" selected={matches!(bus_ctx.source, SourceConfig::TransportNsw)}>
+                            {"Transport NSW trip planner"}
+                        </option>
+                        <option value="open_trip_planner" selected={matches!(bus_ctx.source, SourceConfig::OpenTripPlanner { .. })}>
+                            {"OpenTripPlanner"}
+                        </option>
+                        <option value="gtfs_realtime" selected={matches!(bus_ctx.source, SourceConfig::GtfsRealtime { .. })}>
+                            {"GTFS-Realtime feed + timetable"}
+                        </option>
+                    </select>
+                    <input type="url" name="graphql_url" class="form-control" placeholder="OpenTripPlanner GraphQL URL" value={graphql_url} />
+                    <input type="url" name="trip_updates_url" class="form-control" placeholder="Trip updates feed URL" value={trip_updates_url} />
+                    <input type="url" name="alerts_url" class="form-control" placeholder="Alerts feed URL, optional" value={alerts_url} />
+                    <input type="text" name="api_key" class="form-control" placeholder="API key, if needed" value={api_key} />
+                    <button class="btn btn-primary">{"Save"}</button>
+                </form>
+            </div>
+
+            <div class="flex-fill">
+                <form class="d-flex gap-2" onsubmit={ search_onsubmit }>
+                    <input type="text" name="query" class="form-control" placeholder="Stop name" required={true} />
+                    <button class="btn btn-primary">{"Search"}</button>
+                </form>
+
+                <div>{(*status).clone()}</div>
+
+                {
+                    results.iter().map(|result| {
+                        let is_saved = stops.iter().any(|stop| stop.id == result.id);
+
+                        let add = {
+                            let update_stops = update_stops.clone();
+                            let stops = stops.clone();
+                            let result = result.clone();
+                            Callback::from(move |_| {
+                                let mut stops = stops.clone();
+                                stops.push(result.clone());
+                                update_stops(stops);
+                            })
+                        };
+
+                        html! {
+                            <div class="d-flex gap-1 align-items-center mt-1">
+                                <span class="flex-fill">{&result.name}</span>
+                                <button onclick={add} disabled={is_full || is_saved}>{"Add"}</button>
+                            </div>
+                        }
+                    }).collect::<Html>()
+                }
+            </div>
+        </div>
+    }
+}
@@ -1,16 +1,18 @@
-use chrono::Local;
-use yew::{function_component, html, use_state, Html};
+use yew::{function_component, html, use_context, use_state, Html};
 use yew_hooks::use_interval;
 
+use crate::context::clock::ClockContext;
+
 #[function_component]
 pub fn ClockComponent() -> Html {
-    let current_time = use_state(|| Local::now());
+    let clock = use_context::<ClockContext>().unwrap();
+    let current_time = use_state(|| clock.now());
 
     {
         let state = current_time.clone();
         use_interval(
             move || {
-                state.set(Local::now());
+                state.set(clock.now());
             },
             500,
         );
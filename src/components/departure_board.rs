@@ -0,0 +1,173 @@
+use chrono::Duration;
+use yew::{function_component, html, use_context, use_state, Html};
+use yew_hooks::use_interval;
+
+use crate::{
+    components::service_alerts::AlertBanner,
+    context::{
+        bussin::{BusContext, Departure, Occupancy},
+        clock::ClockContext,
+    },
+};
+
+const MAX_ROWS: usize = 8;
+const REFRESH_MILLIS: u32 = 1_000; // Every second for the leave countdown
+
+#[function_component]
+pub fn DepartureBoard() -> Html {
+    let bus_ctx = use_context::<BusContext>().unwrap();
+    let clock = use_context::<ClockContext>().unwrap();
+    let current_time = use_state(|| clock.now());
+
+    {
+        let state = current_time.clone();
+        use_interval(
+            move || {
+                state.set(clock.now());
+            },
+            REFRESH_MILLIS,
+        );
+    }
+
+    let departures = bus_ctx
+        .data
+        .departures
+        .iter()
+        .map(|departure| {
+            let minutes = departure
+                .departure_time()
+                .signed_duration_since(*current_time)
+                .num_minutes();
+            let walking_minutes = bus_ctx
+                .stops
+                .iter()
+                .find(|stop| stop.id == departure.stop_id)
+                .map(|stop| stop.walking_minutes)
+                .unwrap_or_default();
+            // Time left before having to head out the door
+            let leave_in = departure
+                .departure_time()
+                .signed_duration_since(*current_time)
+                - Duration::minutes(walking_minutes.into());
+            (departure, minutes, walking_minutes, leave_in)
+        })
+        .filter(|(_, minutes, _, _)| *minutes >= 0)
+        .take(MAX_ROWS)
+        .collect::<Vec<_>>();
+
+    // The first one there's still time to walk to
+    let next_catchable = departures.iter().position(|(departure, _, _, leave_in)| {
+        !departure.is_cancelled && *leave_in >= Duration::zero()
+    });
+
+    if departures.is_empty() {
+        return html! {
+            <div>
+                <AlertBanner />
+                <div class="fs-3 text-white">
+                    if bus_ctx.is_loaded {
+                        {"No upcoming departures"}
+                    } else {
+                        {"Loading departures"}
+                    }
+                </div>
+            </div>
+        };
+    }
+
+    html! {
+        <div>
+            <AlertBanner />
+            <table class="table table-sm table-borderless text-white fs-4 mb-0">
+                <tbody>
+                {
+                    departures.iter().enumerate().map(|(i, (departure, minutes, walking_minutes, leave_in))| {
+                        let is_next = next_catchable == Some(i);
+                        let class = match departure.is_cancelled {
+                            true => "text-decoration-line-through opacity-50",
+                            false if *leave_in < Duration::zero() => "opacity-25",
+                            false if is_next && *walking_minutes > 0 => "fw-bold",
+                            false => "",
+                        };
+
+                        let stop = match &departure.platform {
+                            Some(platform) => format!("{} · {platform}", departure.stop_name),
+                            None => departure.stop_name.clone(),
+                        };
+
+                        html! {
+                            <tr class={class}>
+                                <td class="fw-bold text-nowrap">
+                                    {departure.mode.map(|mode| mode.icon()).unwrap_or_default()}
+                                    {" "}{&departure.number}
+                                </td>
+                                <td>
+                                    {&departure.destination}
+                                    <div class="fs-6 opacity-75">{stop}</div>
+                                </td>
+                                <td class="text-nowrap">
+                                    {punctuality(departure)}
+                                    {occupancy(departure)}
+                                </td>
+                                <td class="text-end text-nowrap">
+                                    if departure.is_cancelled {
+                                        {"Cancelled"}
+                                    } else if *minutes == 0 {
+                                        {"Now"}
+                                    } else {
+                                        {format!("{minutes} min")}
+                                    }
+                                    if is_next && *walking_minutes > 0 {
+                                        <div class="fs-6 text-warning">{format_leave_in(*leave_in)}</div>
+                                    }
+                                </td>
+                            </tr>
+                        }
+                    }).collect::<Html>()
+                }
+                </tbody>
+            </table>
+        </div>
+    }
+}
+
+fn format_leave_in(leave_in: Duration) -> String {
+    match leave_in.num_seconds() {
+        0..=59 => "Leave now".to_string(),
+        seconds => format!("Leave in {}:{:02}", seconds / 60, seconds % 60),
+    }
+}
+
+fn punctuality(departure: &Departure) -> Html {
+    if departure.is_scheduled {
+        return html! { <span class="opacity-75">{"scheduled"}</span> };
+    }
+
+    if departure.is_cancelled || !departure.is_realtime {
+        return html! {};
+    }
+
+    match departure.delay_minutes() {
+        Some(delay) if delay > 0 => html! {
+            <span class="text-danger">{format!("+{delay} min late")}</span>
+        },
+        Some(delay) if delay < 0 => html! {
+            <span class="text-info">{format!("{} min early", -delay)}</span>
+        },
+        _ => html! { <span class="text-success">{"On time"}</span> },
+    }
+}
+
+fn occupancy(departure: &Departure) -> Html {
+    let (dots, label) = match departure.occupancy {
+        Some(Occupancy::ManySeats) => ("●○○", "Many seats"),
+        Some(Occupancy::FewSeats) => ("●●○", "Few seats"),
+        Some(Occupancy::StandingOnly) => ("●●●", "Standing only"),
+        Some(Occupancy::Full) => ("✕", "Full"),
+        None => return html! {},
+    };
+
+    html! {
+        <div class="fs-6 opacity-75" title={label}>{dots}</div>
+    }
+}
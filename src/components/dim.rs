@@ -1,26 +1,27 @@
 use chrono::{DateTime, Local, Timelike};
-use yew::{function_component, html, use_state, Html};
+use yew::{function_component, html, use_context, use_state, Html};
 use yew_hooks::use_interval;
 
-const REFRESH_MILLIS: u32 = 900_000; // Every 15 minutes
+use crate::context::clock::ClockContext;
 
-pub fn should_dim() -> bool {
-    let current: DateTime<Local> = Local::now();
+const REFRESH_MILLIS: u32 = 900_000; // Every 15 minutes
 
+pub fn should_dim(current: DateTime<Local>) -> bool {
     let daylight_hours = 7..20;
 
-    return !daylight_hours.contains(&current.hour());
+    !daylight_hours.contains(&current.hour())
 }
 
 #[function_component]
 pub fn DimComponent() -> Html {
-    let is_dim = use_state(|| should_dim());
+    let clock = use_context::<ClockContext>().unwrap();
+    let is_dim = use_state(|| should_dim(clock.now()));
 
     {
         let state = is_dim.clone();
         use_interval(
             move || {
-                state.set(should_dim());
+                state.set(should_dim(clock.now()));
             },
             REFRESH_MILLIS,
         );
@@ -35,3 +36,35 @@ pub fn DimComponent() -> Html {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn at(hour: u32, minute: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 6, 4, hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn brightens_at_seven() {
+        assert!(should_dim(at(6, 59)));
+        assert!(!should_dim(at(7, 0)));
+        assert!(!should_dim(at(7, 1)));
+    }
+
+    #[test]
+    fn dims_at_eight() {
+        assert!(!should_dim(at(19, 59)));
+        assert!(should_dim(at(20, 0)));
+        assert!(should_dim(at(20, 1)));
+    }
+
+    #[test]
+    fn stays_dim_overnight() {
+        assert!(should_dim(at(23, 59)));
+        assert!(should_dim(at(0, 0)));
+        assert!(should_dim(at(3, 0)));
+    }
+}
@@ -1,24 +1,77 @@
 use gloo_storage::{LocalStorage, Storage};
 use web_sys::{wasm_bindgen::JsCast, EventTarget, FormData, HtmlFormElement};
-use yew::{function_component, html, use_context, use_effect_with, Callback, Html, SubmitEvent};
+use yew::{
+    function_component, html, platform::spawn_local, use_context, use_effect_with, use_state,
+    Callback, Html, SubmitEvent,
+};
 
-use crate::context::location::{Coordinates, LocationContext};
+use crate::context::{
+    location::{
+        browser_location, save_location, save_places, search_places, Coordinates, GeocodingResult,
+        Location, LocationAction, LocationContext, LocationSource, Place, MAX_PLACES, STORAGE_KEY,
+    },
+    status::FetchAction,
+};
 
 #[function_component]
 pub fn LocationInput() -> Html {
-    // Should replace this with an address lookup API but I'm lazy A.F.
-
     let location_ctx = use_context::<LocationContext>().unwrap();
+    let results = use_state(Vec::<GeocodingResult>::new);
+    let status = use_state(String::new);
 
     let location_ctx_effect_clone = location_ctx.clone();
     use_effect_with(location_ctx.coordinates.clone(), move |_| {
-        let current_coordinates_result = LocalStorage::get::<Coordinates>("coordinates");
-
-        if current_coordinates_result.is_ok() {
-            location_ctx_effect_clone.dispatch(current_coordinates_result.unwrap());
+        if let Ok(current_location) = LocalStorage::get::<Location>(STORAGE_KEY) {
+            location_ctx_effect_clone.dispatch(LocationAction::Fetch(FetchAction::Finished(Ok(
+                current_location,
+            ))));
         }
     });
 
+    let search_onsubmit = {
+        let results = results.clone();
+        let status = status.clone();
+
+        Callback::from(move |event: SubmitEvent| {
+            event.prevent_default();
+
+            let target: Option<EventTarget> = event.target();
+            let form = target.and_then(|t| t.dyn_into::<HtmlFormElement>().ok());
+
+            if let Some(form) = form {
+                let form_data = FormData::new_with_form(&form).unwrap();
+                let query = form_data.get("query").as_string().unwrap_or_default();
+
+                let results = results.clone();
+                let status = status.clone();
+                status.set("Searching...".to_string());
+
+                spawn_local(async move {
+                    match search_places(query).await {
+                        Ok(places) => {
+                            status.set(format!("{} places found", places.len()));
+                            results.set(places);
+                        }
+                        Err(error) => status.set(format!("Could not search places: {error}")),
+                    }
+                });
+            }
+        })
+    };
+
+    // Picking one saves it straight away, same as typing the numbers in
+    let pick = {
+        let location_ctx = location_ctx.clone();
+        let results = results.clone();
+        let status = status.clone();
+        move |location: Location| {
+            save_location(&location);
+            location_ctx.dispatch(LocationAction::Fetch(FetchAction::Finished(Ok(location))));
+            results.set(Vec::new());
+            status.set(String::new());
+        }
+    };
+
     let location_ctx_submit_clone = location_ctx.clone();
     let form_onsubmit = {
         Callback::from(move |event: SubmitEvent| {
@@ -31,35 +84,123 @@ pub fn LocationInput() -> Html {
                 let form_data = FormData::new_with_form(&form).unwrap();
 
                 // I miss JS sometimes
-                let coordinates = Coordinates {
-                    latitude: form_data
-                        .get("lat")
-                        .as_string()
-                        .unwrap()
-                        .parse::<f32>()
-                        .unwrap(),
-                    longitude: form_data
-                        .get("lon")
-                        .as_string()
-                        .unwrap()
-                        .parse::<f32>()
-                        .unwrap(),
+                let location = Location {
+                    coordinates: Coordinates {
+                        latitude: form_data
+                            .get("lat")
+                            .as_string()
+                            .unwrap()
+                            .parse::<f32>()
+                            .unwrap(),
+                        longitude: form_data
+                            .get("lon")
+                            .as_string()
+                            .unwrap()
+                            .parse::<f32>()
+                            .unwrap(),
+                    },
+                    name: None,
+                    source: LocationSource::Manual,
+                    accuracy_meters: None,
                 };
 
-                LocalStorage::set("coordinates", coordinates.clone()).unwrap();
-                location_ctx_submit_clone.dispatch(coordinates.clone());
+                save_location(&location);
+                location_ctx_submit_clone
+                    .dispatch(LocationAction::Fetch(FetchAction::Finished(Ok(location))));
             }
         })
     };
 
+    let locate_onclick = {
+        let pick = pick.clone();
+        let status = status.clone();
+        Callback::from(move |_| {
+            let pick = pick.clone();
+            let status = status.clone();
+            status.set("Asking the browser...".to_string());
+
+            spawn_local(async move {
+                match browser_location().await {
+                    Ok(location) => pick(location),
+                    Err(error) => {
+                        status.set(format!("Could not get this device's location: {error}"))
+                    }
+                }
+            });
+        })
+    };
+
+    // Same as the bus stops, saved straight away and handed to the provider
+    let update_places = {
+        let location_ctx = location_ctx.clone();
+        move |places: Vec<Place>| {
+            save_places(&places);
+            location_ctx.dispatch(LocationAction::SetPlaces(places));
+        }
+    };
+
+    let places = location_ctx.places.clone();
+    let is_full = places.len() >= MAX_PLACES;
+
+    let saved_places = places
+        .iter()
+        .enumerate()
+        .map(|(i, place)| {
+            let is_primary = place.coordinates == location_ctx.coordinates;
+
+            let make_primary = {
+                let pick = pick.clone();
+                let location = place.location();
+                Callback::from(move |_| pick(location.clone()))
+            };
+
+            let remove = {
+                let update_places = update_places.clone();
+                let places = places.clone();
+                Callback::from(move |_| {
+                    let mut places = places.clone();
+                    places.remove(i);
+                    update_places(places);
+                })
+            };
+
+            html! {
+                <div class="d-flex gap-1 align-items-center mt-1">
+                    <span class="flex-fill">
+                        if is_primary {
+                            {"★ "}
+                        }
+                        {&place.name}
+                        <span class="opacity-75">{format!(" {}", place.timezone.clone().unwrap_or_default())}</span>
+                    </span>
+                    <button onclick={make_primary} disabled={is_primary}>{"Make primary"}</button>
+                    <button onclick={remove}>{"✕"}</button>
+                </div>
+            }
+        })
+        .collect::<Html>();
+
+    let source = match location_ctx.accuracy_meters {
+        Some(accuracy) => format!(
+            "From {}, within {accuracy:.0} m",
+            location_ctx.source.describe()
+        ),
+        None => format!("From {}", location_ctx.source.describe()),
+    };
+
     let location_ctx_onclick_clone = location_ctx.clone();
     let clear_onclick = {
         Callback::from(move |_| {
-            LocalStorage::clear();
+            // Just the location and places, the bins and buses are set up separately
+            LocalStorage::delete(STORAGE_KEY);
+            save_places(&[]);
+            location_ctx_onclick_clone.dispatch(LocationAction::SetPlaces(Vec::new()));
 
-            location_ctx_onclick_clone.dispatch(Coordinates {
-                ..Default::default()
-            });
+            location_ctx_onclick_clone.dispatch(LocationAction::Fetch(FetchAction::Finished(Ok(
+                Location {
+                    ..Default::default()
+                },
+            ))));
         })
     };
 
@@ -73,8 +214,12 @@ pub fn LocationInput() -> Html {
                         <div class="d-flex gap-5">
                             <div>
                                 {"Saved location"}<br/>
+                                if let Some(name) = &location_ctx.name {
+                                    {name}<br/>
+                                }
                                 {"Latitude: "} {current_coordinates.latitude}<br/>
-                                {"Longitude: "} {current_coordinates.longitude}
+                                {"Longitude: "} {current_coordinates.longitude}<br/>
+                                <span class="opacity-75">{source}</span>
                             </div>
 
                             <button onclick={clear_onclick}>
@@ -87,6 +232,58 @@ pub fn LocationInput() -> Html {
                 }
             }
 
+            if let Some(error) = &location_ctx.status.error {
+                <div class="text-danger">{format!("Could not look up location: {error}")}</div>
+            }
+
+
+            if !places.is_empty() {
+                <div class="mt-2">
+                    {format!("Saved places ({}/{MAX_PLACES})", places.len())}
+                    {saved_places}
+                </div>
+            }
+
+            <form class="d-flex gap-2 mt-2" onsubmit={ search_onsubmit }>
+                <input type="text" name="query" class="form-control" placeholder="Suburb or town" required={true} />
+                <button class="btn btn-primary">{"Search"}</button>
+                <button type="button" class="btn btn-secondary text-nowrap" onclick={locate_onclick}>
+                    {"Use this device"}
+                </button>
+            </form>
+
+            <div>{(*status).clone()}</div>
+
+            {
+                results.iter().map(|result| {
+                    let onclick = {
+                        let pick = pick.clone();
+                        let location = result.location();
+                        Callback::from(move |_| pick(location.clone()))
+                    };
+
+                    let place = result.place();
+                    let is_saved = places.iter().any(|saved| saved.coordinates == place.coordinates);
+
+                    let save = {
+                        let update_places = update_places.clone();
+                        let places = places.clone();
+                        Callback::from(move |_| {
+                            let mut places = places.clone();
+                            places.push(place.clone());
+                            update_places(places);
+                        })
+                    };
+
+                    html! {
+                        <div class="d-flex gap-1 align-items-center mt-1">
+                            <span class="flex-fill">{result.display_name()}</span>
+                            <button onclick={onclick}>{"Use"}</button>
+                            <button onclick={save} disabled={is_full || is_saved}>{"Save"}</button>
+                        </div>
+                    }
+                }).collect::<Html>()
+            }
 
             <form class="d-flex flex-column gap-3 mt-2" onsubmit={ form_onsubmit }>
 
@@ -0,0 +1,28 @@
+use yew::{function_component, html, use_context, Html};
+
+use crate::{
+    components::{carousel::CarouselItem, weather::WeatherComponent},
+    context::{location::LocationContext, weather::WeatherProvider},
+};
+
+// A weather slide for every saved place other than the primary one, which already has the first slide
+#[function_component]
+pub fn PlaceSlides() -> Html {
+    let location_ctx = use_context::<LocationContext>().unwrap();
+
+    location_ctx
+        .places
+        .iter()
+        .filter(|place| place.coordinates != location_ctx.coordinates)
+        .map(|place| {
+            html! {
+                <CarouselItem active={false}>
+                    <div class="fs-5 text-white">{&place.name}</div>
+                    <WeatherProvider place={place.clone()}>
+                        <WeatherComponent/>
+                    </WeatherProvider>
+                </CarouselItem>
+            }
+        })
+        .collect::<Html>()
+}
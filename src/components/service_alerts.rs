@@ -0,0 +1,54 @@
+use yew::{function_component, html, use_context, Html};
+
+use crate::context::bussin::{BusContext, ServiceAlert};
+
+// Scrolls across the top of the departure board
+#[function_component]
+pub fn AlertBanner() -> Html {
+    let bus_ctx = use_context::<BusContext>().unwrap();
+
+    if bus_ctx.alerts.is_empty() {
+        return html! {};
+    }
+
+    let text = bus_ctx
+        .alerts
+        .iter()
+        .map(|alert| match alert.description.is_empty() {
+            true => headline(alert),
+            false => format!("{} — {}", headline(alert), alert.description),
+        })
+        .collect::<Vec<_>>()
+        .join("   •   ");
+
+    html! {
+        <div class="bg-warning text-dark fs-5 mb-1 overflow-hidden text-nowrap">
+            <span class="marquee">{format!("⚠ {text}")}</span>
+        </div>
+    }
+}
+
+// Just enough to know there's something to check, sits in the header
+#[function_component]
+pub fn AlertBadge() -> Html {
+    let bus_ctx = use_context::<BusContext>().unwrap();
+
+    let label = match bus_ctx.alerts.as_slice() {
+        [] => return html! {},
+        [alert] => headline(alert),
+        alerts => format!("{} service alerts", alerts.len()),
+    };
+
+    html! {
+        <div class="align-self-start">
+            <span class="badge text-bg-warning fs-6">{format!("⚠ {label}")}</span>
+        </div>
+    }
+}
+
+fn headline(alert: &ServiceAlert) -> String {
+    match alert.lines.is_empty() {
+        true => alert.title.clone(),
+        false => format!("{}: {}", alert.lines.join(", "), alert.title),
+    }
+}
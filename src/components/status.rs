@@ -0,0 +1,104 @@
+use chrono::{DateTime, Duration, Local, Utc};
+use yew::{function_component, html, use_context, use_state, Html};
+use yew_hooks::use_interval;
+
+use crate::{
+    context::{
+        bussin::BusContext, location::LocationContext, status::FetchStatus, weather::WeatherContext,
+    },
+    utils::FetchError,
+};
+
+const REFRESH_MILLIS: u32 = 60_000; // Every minute
+
+// How old departures can get before it's worth pointing out, weather goes by its cache ttl
+fn bus_max_age() -> Duration {
+    Duration::minutes(10)
+}
+
+#[function_component]
+pub fn StatusBadge() -> Html {
+    // Ages are measured against the real time, the fake clock would make everything look ancient
+    let now = use_state(Utc::now);
+
+    {
+        let now = now.clone();
+        use_interval(
+            move || {
+                now.set(Utc::now());
+            },
+            REFRESH_MILLIS,
+        );
+    }
+
+    let location_ctx = use_context::<LocationContext>().unwrap();
+    let weather_ctx = use_context::<WeatherContext>().unwrap();
+    let bus_ctx = use_context::<BusContext>().unwrap();
+
+    let is_buses_stale = bus_ctx
+        .status
+        .last_success
+        .is_some_and(|at| *now - at > bus_max_age());
+
+    let messages = [
+        describe("location", &location_ctx.status, false, *now),
+        describe("weather", &weather_ctx.status, weather_ctx.is_stale, *now),
+        describe("buses", &bus_ctx.status, is_buses_stale, *now),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>();
+
+    let is_loading = location_ctx.status.is_loading
+        || weather_ctx.status.is_loading
+        || bus_ctx.status.is_loading;
+
+    if messages.is_empty() && !is_loading {
+        return html! {};
+    }
+
+    html! {
+        <div class="position-fixed bottom-0 end-0 m-1 d-flex gap-1" style="z-index: 1000;">
+            if is_loading {
+                <span class="badge text-bg-secondary">{"⟳"}</span>
+            }
+            {
+                messages.iter().map(|message| html! {
+                    <span class="badge text-bg-warning">{message}</span>
+                }).collect::<Html>()
+            }
+        </div>
+    }
+}
+
+fn describe(
+    name: &str,
+    status: &FetchStatus,
+    is_stale: bool,
+    now: DateTime<Utc>,
+) -> Option<String> {
+    // Waiting on the settings, not offline
+    if let Some(error @ FetchError::Config(_)) = &status.error {
+        return Some(format!("{name}: {error}"));
+    }
+
+    if let Some(since) = status.failing_since {
+        let since = since.with_timezone(&Local);
+        return Some(format!("{name} offline since {}", since.format("%H:%M")));
+    }
+
+    if !is_stale {
+        return None;
+    }
+
+    let age = now - status.last_success?;
+    Some(format!("{name} {} old", format_age(age)))
+}
+
+fn format_age(age: Duration) -> String {
+    match age {
+        age if age < Duration::hours(1) => format!("{}m", age.num_minutes()),
+        age if age < Duration::days(2) => format!("{}h", age.num_hours()),
+        age => format!("{}d", age.num_days()),
+    }
+}
@@ -0,0 +1,97 @@
+use web_sys::{wasm_bindgen::JsCast, EventTarget, FormData, HtmlFormElement};
+use yew::{function_component, html, use_context, Callback, Html, SubmitEvent};
+
+use crate::context::units::{
+    PrecipitationUnit, TemperatureUnit, Units, UnitsAction, UnitsContext, WindSpeedUnit,
+};
+
+#[function_component]
+pub fn UnitsInput() -> Html {
+    let units_ctx = use_context::<UnitsContext>().unwrap();
+    let units = *units_ctx;
+
+    // Same as everything else, saved straight away and handed to the provider
+    let update_units = {
+        let units_ctx = units_ctx.clone();
+        move |units: Units| {
+            units.save();
+            units_ctx.dispatch(UnitsAction::Set(units));
+        }
+    };
+
+    let form_onsubmit = {
+        let update_units = update_units.clone();
+
+        Callback::from(move |event: SubmitEvent| {
+            event.prevent_default();
+
+            let target: Option<EventTarget> = event.target();
+            let form = target.and_then(|t| t.dyn_into::<HtmlFormElement>().ok());
+
+            if let Some(form) = form {
+                let form_data = FormData::new_with_form(&form).unwrap();
+                let field = |name| form_data.get(name).as_string().unwrap_or_default();
+
+                // The options are the open-meteo names, so they map straight back
+                let units = Units {
+                    temperature: TemperatureUnit::ALL
+                        .into_iter()
+                        .find(|unit| unit.param() == field("temperature"))
+                        .unwrap_or_default(),
+                    wind_speed: WindSpeedUnit::ALL
+                        .into_iter()
+                        .find(|unit| unit.param() == field("wind_speed"))
+                        .unwrap_or_default(),
+                    precipitation: PrecipitationUnit::ALL
+                        .into_iter()
+                        .find(|unit| unit.param() == field("precipitation"))
+                        .unwrap_or_default(),
+                };
+
+                update_units(units);
+            }
+        })
+    };
+
+    let metric_onclick = {
+        let update_units = update_units.clone();
+        Callback::from(move |_| update_units(Units::metric()))
+    };
+
+    let imperial_onclick = Callback::from(move |_| update_units(Units::imperial()));
+
+    html! {
+        <div class="mt-3">
+            <div class="d-flex gap-2 align-items-center">
+                {"Units"}
+                <button onclick={metric_onclick} disabled={units == Units::metric()}>{"Metric"}</button>
+                <button onclick={imperial_onclick} disabled={units == Units::imperial()}>{"Imperial"}</button>
+            </div>
+
+            <form class="d-flex gap-2 mt-2" onsubmit={ form_onsubmit }>
+                <select name="temperature" class="form-select">
+                    {
+                        TemperatureUnit::ALL.iter().map(|unit| html! {
+                            <option value={unit.param()} selected={*unit == units.temperature}>{unit.label()}</option>
+                        }).collect::<Html>()
+                    }
+                </select>
+                <select name="wind_speed" class="form-select">
+                    {
+                        WindSpeedUnit::ALL.iter().map(|unit| html! {
+                            <option value={unit.param()} selected={*unit == units.wind_speed}>{unit.label()}</option>
+                        }).collect::<Html>()
+                    }
+                </select>
+                <select name="precipitation" class="form-select">
+                    {
+                        PrecipitationUnit::ALL.iter().map(|unit| html! {
+                            <option value={unit.param()} selected={*unit == units.precipitation}>{unit.label()}</option>
+                        }).collect::<Html>()
+                    }
+                </select>
+                <button class="btn btn-primary">{"Save"}</button>
+            </form>
+        </div>
+    }
+}
@@ -1,13 +1,12 @@
 use crate::{
     components::{
+        weather_current::CurrentComponent,
         weather_daily::{DailyComponent, DailyComponentProps},
         weather_hourly::HourlyComponent,
     },
-    context::weather::WeatherContext,
+    context::weather::{parse_time, WeatherContext},
 };
 
-use chrono::DateTime;
-
 use yew::prelude::*;
 
 #[function_component]
@@ -15,12 +14,25 @@ pub fn WeatherComponent() -> Html {
     let weather_ctx = use_context::<WeatherContext>().unwrap();
     let weather = weather_ctx.weather.clone();
 
-    let offset_sec = weather.utc_offset_seconds / 60 / 60;
-    let offset_hours = format!("+{offset_sec}:00");
+    let offset = weather.offset();
+
+    // Ids can't have spaces in them, and place names can have anything
+    let chart_id = weather_ctx
+        .cache_key
+        .chars()
+        .map(|c| match c.is_ascii_alphanumeric() {
+            true => c.to_string(),
+            false => format!("_{:x}", c as u32),
+        })
+        .collect::<String>();
 
     html! {
         <>
-            <HourlyComponent data={weather.hourly.clone()} offset_hours={offset_hours.clone()} />
+            if weather_ctx.is_loaded {
+                <CurrentComponent data={weather.current.clone()} />
+            }
+
+            <HourlyComponent id={format!("chart_{chart_id}")} data={weather.hourly.clone()} offset={offset} />
 
             <div class="card-group text-white">
             {
@@ -30,21 +42,29 @@ pub fn WeatherComponent() -> Html {
                     let precipitation = weather.daily.precipitation_sum.clone()[i];
                     let precipitation_probability_max = weather.daily.precipitation_probability_max.clone()[i];
                     let code = weather.daily.weather_code.clone()[i];
+                    // Older cached forecasts don't have these
+                    let extra = |values: &Vec<f32>| values.get(i).copied().unwrap_or_default();
 
-                    let date = DateTime::parse_from_rfc3339(&format!("{time}T00:00:00{offset_hours}"));
-                    let sunrise = DateTime::parse_from_rfc3339(&format!("{}:00{offset_hours}", weather.daily.sunrise.clone()[i]));
-                    let sunset = DateTime::parse_from_rfc3339(&format!("{}:00{offset_hours}", weather.daily.sunset.clone()[i]));
+                    let date = parse_time(time, offset);
+                    let sunrise = parse_time(&weather.daily.sunrise[i], offset);
+                    let sunset = parse_time(&weather.daily.sunset[i], offset);
 
-                    if date.is_ok() {
+                    if let (Some(date), Some(sunrise), Some(sunset)) = (date, sunrise, sunset) {
                         let props = DailyComponentProps {
                             weather_code: code.to_owned(),
                             temp_max: temp_max.to_owned(),
                             temp_min: temp_min.to_owned(),
                             precipitation_sum: precipitation.to_owned(),
                             precipitation_probability_max: precipitation_probability_max.to_owned(),
-                            date: date.unwrap().to_owned().into(),
-                            sunrise: sunrise.unwrap().to_owned().into(),
-                            sunset: sunset.unwrap().to_owned().into(),
+                            date,
+                            sunrise,
+                            sunset,
+                            apparent_max: extra(&weather.daily.apparent_temperature_max),
+                            apparent_min: extra(&weather.daily.apparent_temperature_min),
+                            humidity: extra(&weather.daily.relative_humidity_2m_mean),
+                            wind_speed_max: extra(&weather.daily.wind_speed_10m_max),
+                            wind_gusts_max: extra(&weather.daily.wind_gusts_10m_max),
+                            wind_direction: weather.daily.wind_direction_10m_dominant.get(i).copied().unwrap_or_default(),
                         };
                         html!{
                             <DailyComponent ..props.clone() />
@@ -56,6 +76,14 @@ pub fn WeatherComponent() -> Html {
                 }).collect::<Html>()
             }
             </div>
+
+            if weather_ctx.is_stale {
+                <div class="text-warning">{"Showing saved forecast"}</div>
+            }
+
+            if let Some(error) = &weather_ctx.status.error {
+                <div class="text-danger">{format!("Could not update weather: {error}")}</div>
+            }
         </>
     }
 }
@@ -0,0 +1,40 @@
+use yew::{function_component, html, use_context, Html, Properties};
+
+use crate::{
+    components::weather_daily::{CodeIconComponent, WindArrowComponent},
+    context::{units::UnitsContext, weather::WeatherCurrent},
+};
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct CurrentComponentProps {
+    pub data: WeatherCurrent,
+}
+
+// Right now, big enough to read from across the room
+#[function_component]
+pub fn CurrentComponent(props: &CurrentComponentProps) -> Html {
+    let current = &props.data;
+    let units = *use_context::<UnitsContext>().unwrap();
+    let temperature = units.temperature.label();
+    let wind_speed = units.wind_speed.label();
+
+    html! {
+    <div class="d-flex align-items-center gap-4 text-white">
+        <CodeIconComponent code={current.weather_code} is_day={current.is_day()} />
+        <div class="display-4 fw-bold text-nowrap">
+            {format!("{:.0} {temperature}", current.temperature_2m)}
+        </div>
+        <div class="fs-5">
+            <div>{format!("Feels like {:.0} {temperature}", current.apparent_temperature)}</div>
+            <div>{format!("Humidity {}%", current.relative_humidity_2m)}</div>
+        </div>
+        <div class="fs-5">
+            <div>
+                <WindArrowComponent direction={current.wind_direction_10m} />
+                {format!(" Wind {:.0} {wind_speed}", current.wind_speed_10m)}
+            </div>
+            <div>{format!("Gusts {:.0} {wind_speed}", current.wind_gusts_10m)}</div>
+        </div>
+    </div>
+    }
+}
@@ -1,20 +1,31 @@
-use chrono::{DateTime, Local};
-use yew::{function_component, html, Html, Properties};
+use chrono::{DateTime, FixedOffset};
+use yew::{function_component, html, use_context, Html, Properties};
+
+use crate::context::units::UnitsContext;
 
 #[derive(Clone, PartialEq, Properties)]
 pub struct DailyComponentProps {
     pub weather_code: i32,
-    pub date: DateTime<Local>,
+    // All in the place's own offset, not the kiosk's
+    pub date: DateTime<FixedOffset>,
     pub temp_min: f32,
     pub temp_max: f32,
     pub precipitation_sum: f32,
     pub precipitation_probability_max: i32,
-    pub sunrise: DateTime<Local>,
-    pub sunset: DateTime<Local>,
+    pub sunrise: DateTime<FixedOffset>,
+    pub sunset: DateTime<FixedOffset>,
+    pub apparent_max: f32,
+    pub apparent_min: f32,
+    pub humidity: f32,
+    pub wind_speed_max: f32,
+    pub wind_gusts_max: f32,
+    pub wind_direction: i32,
 }
 
 #[function_component]
 pub fn DailyComponent(props: &DailyComponentProps) -> Html {
+    let units = *use_context::<UnitsContext>().unwrap();
+
     html! {
     <div class="card">
         <div class="card-header text-center p-0 text-white">
@@ -23,14 +34,21 @@ pub fn DailyComponent(props: &DailyComponentProps) -> Html {
         <div class="card-body d-flex flex-column align-items-center gap-1 p-0">
             <CodeIconComponent code={props.weather_code} />
             <div class="text-nowrap text-white fw-bold fs-5">
-                {format!("{:.0} - {:.0}  ºC", props.temp_max, props.temp_min)}
+                {format!("{:.0} - {:.0}  {}", props.temp_max, props.temp_min, units.temperature.label())}
+            </div>
+            <div class="text-nowrap text-white">
+                {format!("Feels {:.0} - {:.0}  {:.0}%", props.apparent_max, props.apparent_min, props.humidity)}
             </div>
             <div class="text-nowrap text-white fw-bold">
                 { format!("{} - {}", props.sunrise.format("%H:%M"), props.sunset.format("%H:%M")) }
             </div>
+            <div class="text-nowrap text-white fw-bold">
+                <WindArrowComponent direction={props.wind_direction} />
+                {format!(" {:.0} ({:.0}) {}", props.wind_speed_max, props.wind_gusts_max, units.wind_speed.label())}
+            </div>
             if props.precipitation_sum > 0.0 {
                 <div class="text-white fw-bold">
-                    {format!("{}mm {}%", props.precipitation_sum, props.precipitation_probability_max)}
+                    {format!("{}{} {}%", props.precipitation_sum, units.precipitation.label(), props.precipitation_probability_max)}
                 </div>
             }
         </div>
@@ -39,13 +57,18 @@ pub fn DailyComponent(props: &DailyComponentProps) -> Html {
 }
 
 #[derive(Clone, PartialEq, Properties)]
-struct CodeIconProps {
-    code: i32,
+pub struct CodeIconProps {
+    pub code: i32,
+    // Daily forecasts are always the day version
+    #[prop_or(true)]
+    pub is_day: bool,
 }
 
 #[function_component]
-fn CodeIconComponent(props: &CodeIconProps) -> Html {
+pub fn CodeIconComponent(props: &CodeIconProps) -> Html {
     let class = match props.code {
+        0 | 1 if !props.is_day => "wi-night-clear",
+        2 | 3 if !props.is_day => "wi-night-alt-cloudy",
         0 | 1 => "wi-day-sunny",
         2 | 3 => "wi-day-cloudy",
         45 | 48 => "wi-fog",
@@ -55,7 +78,7 @@ fn CodeIconComponent(props: &CodeIconProps) -> Html {
         66 | 67 => "wi-rain-mix",
         71 | 73 | 75 => "wi-snowflake-cold",
         77 => "wi-snow-wind",
-        80 | 81 | 82 => "wi-rain",
+        80..=82 => "wi-rain",
         85 | 86 => "wi-day-snow-thunderstorm",
         96 | 99 => "wi-day-thunderstorm",
         95 => "wi-day-thunderstorm",
@@ -69,3 +92,19 @@ fn CodeIconComponent(props: &CodeIconProps) -> Html {
         </div>
     }
 }
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct WindArrowProps {
+    // Degrees the wind is coming from, like the API gives it
+    pub direction: i32,
+}
+
+// Points the way the wind is blowing, so a southerly points up
+#[function_component]
+pub fn WindArrowComponent(props: &WindArrowProps) -> Html {
+    let style = format!("transform: rotate({}deg)", props.direction);
+
+    html! {
+        <i class="wi wi-direction-down" style={style}></i>
+    }
+}
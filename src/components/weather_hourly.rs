@@ -7,52 +7,95 @@ use charming::{
     series::Line,
     Chart, WasmRenderer,
 };
-use chrono::{DateTime, Local};
-use yew::{function_component, html, use_effect_with, Html, Properties};
+use chrono::{DateTime, FixedOffset, Local};
+use yew::{function_component, html, use_context, use_effect_with, AttrValue, Html, Properties};
 use yew_hooks::use_async;
 
-use crate::context::weather::WeatherHourly;
+use crate::context::{
+    clock::ClockContext,
+    units::UnitsContext,
+    weather::{parse_time, WeatherHourly},
+};
+
+// Charted from the next whole hour, both ends included
+const WINDOW_HOURS: usize = 48;
 
 #[derive(Clone, PartialEq, Properties)]
 pub struct HourlyComponentProps {
+    // Every place on the carousel has its own chart to render into
+    pub id: AttrValue,
     pub data: WeatherHourly,
-    pub offset_hours: String,
+    pub offset: FixedOffset,
+}
+
+// Index into the hourly data and when it is, for every hour in the window
+pub fn upcoming_hours(
+    data: &WeatherHourly,
+    offset: FixedOffset,
+    now: DateTime<Local>,
+) -> Vec<(usize, DateTime<FixedOffset>)> {
+    data.time
+        .iter()
+        .enumerate()
+        .filter_map(|(i, time)| parse_time(time, offset).map(|date| (i, date)))
+        .filter(|(_, date)| *date >= now)
+        .take(WINDOW_HOURS + 1)
+        .collect()
 }
 
 #[function_component]
 pub fn HourlyComponent(props: &HourlyComponentProps) -> Html {
-    let current_time = Local::now();
+    let current_time = use_context::<ClockContext>().unwrap().now();
+    let units = *use_context::<UnitsContext>().unwrap();
+
+    // Legend, series and what's switched on all go by name
+    let precipitation_name = format!("Precipitation ({})", units.precipitation.label());
+    let wind_name = format!("Wind ({})", units.wind_speed.label());
+    let gusts_name = format!("Gusts ({})", units.wind_speed.label());
 
     let mut time = Vec::new();
     let mut temp = Vec::new();
     let mut rain = Vec::new();
     let mut uv: Vec<f32> = Vec::new();
+    let mut feels_like = Vec::new();
+    let mut humidity = Vec::new();
+    let mut wind = Vec::new();
+    let mut gusts = Vec::new();
 
-    let offset_hours = props.offset_hours.clone();
-
-    for (i, time_stamp) in props.data.time.clone().iter().enumerate() {
-        if time.len() > 48 {
-            break;
-        }
+    // Older cached forecasts don't have these
+    let extra = |values: &Vec<f32>, i: usize| values.get(i).copied().unwrap_or_default();
 
-        // let precipitation = weather.hourly.precipitation.clone()[i];
-
-        let date = DateTime::parse_from_rfc3339(&format!("{time_stamp}:00{offset_hours}"));
-
-        if date.is_ok() && date.unwrap() >= current_time {
-            time.push(format!("{}", date.unwrap().format("%H:%M")));
-            temp.push(props.data.temperature_2m[i]);
-            rain.push(props.data.precipitation[i]);
-            uv.push(props.data.uv_index[i]);
-        } else {
-        }
+    for (i, date) in upcoming_hours(&props.data, props.offset, current_time) {
+        time.push(format!("{}", date.format("%H:%M")));
+        temp.push(props.data.temperature_2m[i]);
+        rain.push(props.data.precipitation[i]);
+        uv.push(props.data.uv_index[i]);
+        feels_like.push(extra(&props.data.apparent_temperature, i));
+        humidity.push(extra(&props.data.relative_humidity_2m, i));
+        wind.push(extra(&props.data.wind_speed_10m, i));
+        gusts.push(extra(&props.data.wind_gusts_10m, i));
     }
 
     let f = use_async::<_, _, ()>({
         let chart = Chart::new()
             .legend(
                 Legend::new()
-                    .data(vec!["Temperature", "Precipitation", "UV"])
+                    .data(vec![
+                        "Temperature",
+                        "Feels like",
+                        &precipitation_name,
+                        "UV",
+                        &wind_name,
+                        &gusts_name,
+                        "Humidity",
+                    ])
+                    // The extras are there to tap on, too busy to show all the time
+                    .selected(vec![
+                        ("Feels like", false),
+                        (&wind_name, false),
+                        (&gusts_name, false),
+                        ("Humidity", false),
+                    ])
                     .text_style(TextStyle::new().color("white")),
             )
             .x_axis(
@@ -66,6 +109,8 @@ pub fn HourlyComponent(props: &HourlyComponentProps) -> Html {
                 // Temp Lines
                 Axis::new()
                     .type_(AxisType::Value)
+                    .name(units.temperature.label())
+                    .name_text_style(TextStyle::new().color("white"))
                     .axis_label(AxisLabel::new().color("white"))
                     // Doesn't work https://github.com/yuankunzhang/charming/pull/67
                     // .axis_label(AxisLabel::new().formatter("{value} °C"))
@@ -79,6 +124,10 @@ pub fn HourlyComponent(props: &HourlyComponentProps) -> Html {
                     .split_line(SplitLine::new().line_style(LineStyle::new().opacity(0)))
                     .max(11),
             )
+            .y_axis(
+                // Wind and humidity, both fit under 100
+                Axis::new().type_(AxisType::Value).show(false).max(100),
+            )
             .series(
                 Line::new()
                     .name("Temperature")
@@ -97,7 +146,15 @@ pub fn HourlyComponent(props: &HourlyComponentProps) -> Html {
             )
             .series(
                 Line::new()
-                    .name("Precipitation")
+                    .name("Feels like")
+                    .data(feels_like.clone())
+                    .show_symbol(false)
+                    .item_style(ItemStyle::new().color("lightgrey"))
+                    .line_style(LineStyle::new().width(3).color("lightgrey")),
+            )
+            .series(
+                Line::new()
+                    .name(&precipitation_name)
                     .data(rain.clone())
                     .y_axis_index(1)
                     .show_symbol(false)
@@ -113,22 +170,123 @@ pub fn HourlyComponent(props: &HourlyComponentProps) -> Html {
                     .item_style(ItemStyle::new().color("orange"))
                     .line_style(LineStyle::new().width(3).color("orange")),
             )
+            .series(
+                Line::new()
+                    .name(&wind_name)
+                    .data(wind.clone())
+                    .y_axis_index(2)
+                    .show_symbol(false)
+                    .item_style(ItemStyle::new().color("lightgreen"))
+                    .line_style(LineStyle::new().width(3).color("lightgreen")),
+            )
+            .series(
+                Line::new()
+                    .name(&gusts_name)
+                    .data(gusts.clone())
+                    .y_axis_index(2)
+                    .show_symbol(false)
+                    .item_style(ItemStyle::new().color("green"))
+                    .line_style(LineStyle::new().width(2).color("green")),
+            )
+            .series(
+                Line::new()
+                    .name("Humidity")
+                    .data(humidity.clone())
+                    .y_axis_index(2)
+                    .show_symbol(false)
+                    .item_style(ItemStyle::new().color("lightblue"))
+                    .line_style(LineStyle::new().width(2).color("lightblue")),
+            )
             .grid(Grid::new().top(24).left(24).right(24).bottom(20));
 
         let renderer = WasmRenderer::new(780, 170);
+        let id = props.id.clone();
 
         async move {
-            renderer.render("chart", &chart).unwrap();
+            renderer.render(&id, &chart).unwrap();
             Ok(())
         }
     });
 
-    use_effect_with(time.clone(), move |_| {
+    use_effect_with((time.clone(), units), move |_| {
         f.run();
         || ()
     });
 
     html! {
-        <div id="chart"></div>
+        <div id={props.id.clone()}></div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, NaiveDate, TimeZone};
+
+    use super::*;
+
+    fn sydney() -> FixedOffset {
+        FixedOffset::east_opt(10 * 60 * 60).unwrap()
+    }
+
+    // Four days of hours starting at midnight on the 1st of June
+    fn hourly() -> WeatherHourly {
+        let start = NaiveDate::from_ymd_opt(2024, 6, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+
+        WeatherHourly {
+            time: (0..96)
+                .map(|hour| {
+                    (start + Duration::hours(hour))
+                        .format("%Y-%m-%dT%H:%M")
+                        .to_string()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn at(day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+        sydney()
+            .with_ymd_and_hms(2024, 6, day, hour, minute, 0)
+            .unwrap()
+            .with_timezone(&Local)
+    }
+
+    #[test]
+    fn starts_at_the_next_hour() {
+        let hours = upcoming_hours(&hourly(), sydney(), at(1, 5, 30));
+        let (index, first) = hours[0];
+
+        assert_eq!(index, 6);
+        assert_eq!(
+            first,
+            sydney().with_ymd_and_hms(2024, 6, 1, 6, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn includes_the_current_hour_on_the_dot() {
+        let hours = upcoming_hours(&hourly(), sydney(), at(1, 5, 0));
+
+        assert_eq!(hours[0].0, 5);
+    }
+
+    #[test]
+    fn covers_48_hours() {
+        let hours = upcoming_hours(&hourly(), sydney(), at(1, 5, 30));
+        let (_, first) = hours[0];
+        let (_, last) = hours[hours.len() - 1];
+
+        assert_eq!(hours.len(), WINDOW_HOURS + 1);
+        assert_eq!(last - first, Duration::hours(48));
+    }
+
+    #[test]
+    fn runs_short_at_the_end_of_the_forecast() {
+        let hours = upcoming_hours(&hourly(), sydney(), at(4, 12, 0));
+
+        assert_eq!(hours.len(), 12);
     }
 }
@@ -1,3 +1,6 @@
 pub mod bussin;
+pub mod clock;
 pub mod location;
+pub mod status;
+pub mod units;
 pub mod weather;
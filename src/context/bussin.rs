@@ -1,20 +1,165 @@
-use chrono::{DateTime, FixedOffset};
+use chrono::{DateTime, FixedOffset, Local, Utc};
 use gloo_console::log;
-use serde_json::Value;
+use gloo_storage::{LocalStorage, Storage};
+use serde::Deserialize;
 use std::rc::Rc;
 use yew::{platform::spawn_local, prelude::*};
+use yew_hooks::use_interval;
 
-use crate::utils::fetch;
+use crate::context::{
+    clock::ClockContext,
+    status::{FetchAction, FetchStatus},
+};
+
+pub mod gtfs;
+pub mod gtfs_realtime;
+pub mod open_trip_planner;
+pub mod source;
+pub mod transport_nsw;
+
+use gtfs::Timetable;
+use source::SourceConfig;
+
+const STORAGE_KEY: &str = "bus_stops";
+const REFRESH_MILLIS: u32 = 60_000; // Every minute
+
+// Each stop is its own request, go easy on the API
+pub const MAX_STOPS: usize = 5;
+
+// How many timetabled departures to fill in per stop when offline
+const MAX_SCHEDULED: usize = 10;
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct BusCtx {
     pub is_loaded: bool,
     pub data: BusData,
+    pub status: FetchStatus,
+    pub stops: Vec<BusStop>,
+    pub source: SourceConfig,
+    pub alerts: Vec<ServiceAlert>,
+    // Read from IndexedDB once, then swapped out whenever it's imported or cleared
+    pub timetable: Option<Rc<Timetable>>,
+}
+
+pub enum BusAction {
+    Fetch(FetchAction<BusData>),
+    // Already saved, this just gets the provider to pick them up
+    SetStops(Vec<BusStop>),
+    // Same as stops, saved before it gets here
+    SetSource(SourceConfig),
+    // From the offline timetable, the fetch status is left as it was
+    Scheduled(Vec<Departure>),
+    SetAlerts(Vec<ServiceAlert>),
+    // Already saved, or cleared, same as stops
+    SetTimetable(Option<Rc<Timetable>>),
 }
 
 #[derive(Debug, PartialEq, Clone, serde::Serialize, serde::Deserialize, Default)]
 pub struct BusStopsStorage {
-    pub bus_stops: Vec<String>,
+    pub bus_stops: Vec<BusStop>,
+}
+
+#[derive(Debug, PartialEq, Clone, serde::Serialize, serde::Deserialize, Default)]
+#[serde(from = "SavedStop")]
+pub struct BusStop {
+    pub id: String,
+    pub name: String,
+    // Which services to show for this stop
+    pub modes: Vec<TransportMode>,
+    // How long it takes to get there from the front door
+    pub walking_minutes: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SavedStop {
+    // Stops used to be saved as just the id
+    Id(String),
+    Stop {
+        id: String,
+        #[serde(default)]
+        name: String,
+        #[serde(default = "default_modes")]
+        modes: Vec<TransportMode>,
+        #[serde(default)]
+        walking_minutes: u32,
+    },
+}
+
+impl From<SavedStop> for BusStop {
+    fn from(stop: SavedStop) -> Self {
+        match stop {
+            SavedStop::Id(id) => BusStop {
+                name: id.clone(),
+                id,
+                modes: default_modes(),
+                walking_minutes: 0,
+            },
+            SavedStop::Stop {
+                id,
+                name,
+                modes,
+                walking_minutes,
+            } => BusStop {
+                id,
+                name,
+                modes,
+                walking_minutes,
+            },
+        }
+    }
+}
+
+// Only buses were shown before modes could be picked
+fn default_modes() -> Vec<TransportMode> {
+    vec![TransportMode::Bus]
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum TransportMode {
+    Train,
+    Metro,
+    LightRail,
+    Bus,
+    Coach,
+    Ferry,
+    SchoolBus,
+}
+
+impl TransportMode {
+    pub const ALL: [TransportMode; 7] = [
+        TransportMode::Train,
+        TransportMode::Metro,
+        TransportMode::LightRail,
+        TransportMode::Bus,
+        TransportMode::Coach,
+        TransportMode::Ferry,
+        TransportMode::SchoolBus,
+    ];
+
+    pub fn icon(&self) -> &'static str {
+        match self {
+            TransportMode::Train => "🚆",
+            TransportMode::Metro => "🚇",
+            TransportMode::LightRail => "🚊",
+            TransportMode::Bus => "🚌",
+            TransportMode::Coach => "🚍",
+            TransportMode::Ferry => "⛴",
+            TransportMode::SchoolBus => "🚸",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TransportMode::Train => "Train",
+            TransportMode::Metro => "Metro",
+            TransportMode::LightRail => "Light rail",
+            TransportMode::Bus => "Bus",
+            TransportMode::Coach => "Coach",
+            TransportMode::Ferry => "Ferry",
+            TransportMode::SchoolBus => "School bus",
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Default)]
@@ -22,21 +167,111 @@ pub struct BusData {
     pub departures: Vec<Departure>,
 }
 
+// Track work, replacement buses and the like
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ServiceAlert {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    // Route numbers it's about, empty when it's the whole stop
+    pub lines: Vec<String>,
+}
+
 #[derive(Debug, Clone, PartialEq, Default)]
 pub struct Departure {
+    // The saved stop this was looked up for
+    pub stop_id: String,
     pub number: String,
+    pub mode: Option<TransportMode>,
     pub stop_name: String,
-    pub departure_time: DateTime<FixedOffset>,
+    // Where it's headed, as shown on the front of the bus
+    pub destination: String,
+    pub platform: Option<String>,
+    pub planned_time: DateTime<FixedOffset>,
+    // Only there when the vehicle is being tracked
+    pub estimated_time: Option<DateTime<FixedOffset>>,
+    pub is_realtime: bool,
+    pub occupancy: Option<Occupancy>,
     pub is_cancelled: bool,
+    // Came from the offline timetable rather than the API
+    pub is_scheduled: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Occupancy {
+    ManySeats,
+    FewSeats,
+    StandingOnly,
+    Full,
+}
+
+impl Occupancy {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "MANY_SEATS" | "EMPTY" => Some(Occupancy::ManySeats),
+            "FEW_SEATS" => Some(Occupancy::FewSeats),
+            "STANDING_ONLY" | "STANDING_ROOM_ONLY" => Some(Occupancy::StandingOnly),
+            "FULL" | "CRUSHED_STANDING_ROOM_ONLY" | "NOT_ACCEPTING_PASSENGERS" => {
+                Some(Occupancy::Full)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Departure {
+    // Best guess at when it actually leaves
+    pub fn departure_time(&self) -> DateTime<FixedOffset> {
+        self.estimated_time.unwrap_or(self.planned_time)
+    }
+
+    // Positive when running late, None without live tracking
+    pub fn delay_minutes(&self) -> Option<i64> {
+        self.estimated_time
+            .map(|estimated| (estimated - self.planned_time).num_minutes())
+    }
 }
 
 impl Reducible for BusCtx {
-    type Action = BusData;
+    type Action = BusAction;
 
-    fn reduce(self: Rc<Self>, data: Self::Action) -> Rc<Self> {
-        BusCtx {
-            is_loaded: true,
-            data: data,
+    fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
+        match action {
+            BusAction::SetStops(stops) => BusCtx {
+                stops,
+                ..(*self).clone()
+            },
+            BusAction::SetSource(source) => BusCtx {
+                source,
+                ..(*self).clone()
+            },
+            BusAction::SetAlerts(alerts) => BusCtx {
+                alerts,
+                ..(*self).clone()
+            },
+            BusAction::SetTimetable(timetable) => BusCtx {
+                timetable,
+                ..(*self).clone()
+            },
+            BusAction::Scheduled(departures) => BusCtx {
+                is_loaded: true,
+                data: BusData { departures },
+                ..(*self).clone()
+            },
+            BusAction::Fetch(FetchAction::Started) => BusCtx {
+                status: self.status.started(),
+                ..(*self).clone()
+            },
+            BusAction::Fetch(FetchAction::Finished(Ok(data))) => BusCtx {
+                is_loaded: true,
+                data,
+                status: self.status.succeeded(Utc::now()),
+                ..(*self).clone()
+            },
+            BusAction::Fetch(FetchAction::Finished(Err(error))) => BusCtx {
+                status: self.status.failed(error),
+                ..(*self).clone()
+            },
         }
         .into()
     }
@@ -52,43 +287,43 @@ pub struct BusProviderProps {
 
 #[function_component]
 pub fn BusProvider(props: &BusProviderProps) -> Html {
+    let clock = use_context::<ClockContext>().unwrap();
     let data = use_reducer(|| BusCtx {
         is_loaded: false,
         data: BusData {
             ..Default::default()
         },
+        status: FetchStatus::default(),
+        stops: load_stops(),
+        source: SourceConfig::load(),
+        alerts: Vec::new(),
+        timetable: None,
     });
 
-    // let bus_ctx = use_context::<BusContext>().unwrap();
-
-    let update_every_millis = 1000 * 60 * 60;
-    use_effect(move || {
-        // let stops_to_load = LocalStorage::get::<BusStopsStorage>("bus_stops");
-
-        // if stops_to_load.is_err() {
-        //     log!("Could not load stops from storage");
-        //     return;
-        // }
-
-        // let stops = stops_to_load.unwrap();
-        // if stops.bus_stops.len() == 0 {
-        //     return;
-        // }
-
-        // if stops.bus_stops.len() > 5 {
-        //     log!("Probably loading too much stops for now??");
-        //     return;
-        // }
-
+    // Too big to read on every poll, it's only needed once the API falls over anyway
+    let data_clone = data.clone();
+    use_effect_with((), move |_| {
         spawn_local(async move {
-            // let data = fetch_departures("G123123".to_string()).await;
-            // data_clone.dispatcher(BusCtx {
-            //     is_loaded: true,
-            //     data: BusData { departures: data }
-            // })
+            let timetable = Timetable::load().await;
+            data_clone.dispatch(BusAction::SetTimetable(timetable.map(Rc::new)));
         });
     });
 
+    // Straight away, and again whenever the saved stops or where they come from change
+    let data_clone = data.clone();
+    let clock_clone = clock.clone();
+    use_effect_with((data.stops.clone(), data.source.clone()), move |_| {
+        spawn_local(load_departures(data_clone, clock_clone.now()));
+    });
+
+    let data_clone1 = data.clone();
+    use_interval(
+        move || {
+            spawn_local(load_departures(data_clone1.clone(), clock.now()));
+        },
+        REFRESH_MILLIS,
+    );
+
     html! {
         <ContextProvider<BusContext> context={data}>
             {props.children.clone()}
@@ -96,44 +331,81 @@ pub fn BusProvider(props: &BusProviderProps) -> Html {
     }
 }
 
-// https://transportnsw.info/api/trip/v1/departure-list-request?name=G12312312&type=stop&depArrMacro=dep&depType=stopEvents&excludedModes=2,9,11,1,4,7
+pub fn load_stops() -> Vec<BusStop> {
+    let Ok(stops) = LocalStorage::get::<BusStopsStorage>(STORAGE_KEY) else {
+        log!("Could not load stops from storage");
+        return Vec::new();
+    };
+
+    if stops.bus_stops.len() > MAX_STOPS {
+        log!("Probably loading too much stops for now??");
+    }
+
+    stops.bus_stops.into_iter().take(MAX_STOPS).collect()
+}
+
+pub fn save_stops(stops: &[BusStop]) {
+    let storage = BusStopsStorage {
+        bus_stops: stops.to_vec(),
+    };
+    if let Err(error) = LocalStorage::set(STORAGE_KEY, storage) {
+        log!(format!("Could not save bus stops: {error}"));
+    }
+}
 
-async fn fetch_departures(stop_number: String) -> Vec<Departure> {
-    let params = [
-        ["name", &stop_number.to_string()],
-        ["depArrMacro", &"dep".to_string()],
-        ["type", &"stop".to_string()],
-        ["depType", &"stopEvents".to_string()],
-        ["excludedModes", &"2,9,11,1,4,7".to_string()], // Only show busses for now
-    ]
-    .map(|x| x.join("="))
-    .join("&");
+async fn load_departures(bus_ctx: BusContext, now: DateTime<Local>) {
+    let stops = bus_ctx.stops.clone();
 
-    let url = "https://transportnsw.info/api/trip/v1/departure-list-request?".to_string() + &params;
+    if stops.is_empty() {
+        bus_ctx.dispatch(BusAction::Fetch(FetchAction::Finished(Ok(
+            BusData::default(),
+        ))));
+        return;
+    }
 
-    let response = fetch::<String>(url).await;
-    log!(format!("{:?}", response));
+    bus_ctx.dispatch(BusAction::Fetch(FetchAction::Started));
 
-    let data: Value = serde_json::from_str(&response).unwrap();
+    let timetable = bus_ctx.timetable.clone();
+    let source = bus_ctx.source.source(timetable.clone());
+    let results = source.departures(&stops, now).await;
 
-    let stop_events = data["stopEvents"].as_array().unwrap();
     let mut departures = Vec::new();
+    let mut scheduled = Vec::new();
+    let mut error = None;
+    for (stop, result) in stops.iter().zip(results) {
+        match result {
+            Ok(stop_departures) => departures.extend(stop_departures),
+            Err(stop_error) => {
+                error = Some(stop_error);
+                if let Some(timetable) = &timetable {
+                    scheduled.extend(timetable.departures(stop, now, MAX_SCHEDULED));
+                }
+            }
+        }
+    }
 
-    for stop in stop_events {
-        departures.push(Departure {
-            departure_time: DateTime::parse_from_rfc3339(stop["departureTime"].as_str().unwrap())
-                .unwrap(),
-            number: stop["transportation"]["number"]
-                .as_str()
-                .unwrap()
-                .to_string(),
-            stop_name: stop["location"]["disassembledName"]
-                .as_str()
-                .unwrap()
-                .to_string(),
-            is_cancelled: stop["isCancelled"].as_bool().unwrap(),
-        });
+    // One stop failing shouldn't blank out the others
+    let result = match (departures.is_empty(), error) {
+        (true, Some(error)) => Err(error),
+        _ => {
+            departures.append(&mut scheduled);
+            departures.sort_by_key(|departure| departure.departure_time());
+            Ok(BusData { departures })
+        }
+    };
+
+    let is_offline = result.is_err();
+    bus_ctx.dispatch(BusAction::Fetch(FetchAction::Finished(result)));
+
+    // Still worth knowing roughly when the next one is due
+    if is_offline && !scheduled.is_empty() {
+        scheduled.sort_by_key(|departure| departure.planned_time);
+        bus_ctx.dispatch(BusAction::Scheduled(scheduled));
     }
 
-    return departures;
+    // Nice to have, not worth flagging the buses as offline over
+    match source.alerts(&stops, now).await {
+        Ok(alerts) => bus_ctx.dispatch(BusAction::SetAlerts(alerts)),
+        Err(error) => log!(format!("Could not load service alerts: {error}")),
+    }
 }
@@ -0,0 +1,864 @@
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, TimeZone};
+use gloo_console::log;
+use gloo_storage::{LocalStorage, Storage};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::str::Lines;
+
+use crate::{
+    context::bussin::{BusStop, Departure, TransportMode},
+    idb,
+    utils::{fetch_text_with, FetchError, FetchOptions},
+};
+
+// Just enough of GTFS static to know when things are due at the saved stops.
+// Only the calls at those stops are kept, but a busy station can still run to
+// megabytes, so it goes in IndexedDB rather than eating LocalStorage's quota.
+
+pub const STORAGE_KEY: &str = "bus_timetable";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct Timetable {
+    pub stops: Vec<StopTimetable>,
+    services: HashMap<String, Service>,
+    // Every station and standalone stop in the feed, to pick the saved ones from
+    #[serde(default)]
+    places: Vec<FeedStop>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct FeedStop {
+    id: String,
+    name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StopTimetable {
+    // The saved stop, platforms are folded into their station
+    pub stop_id: String,
+    pub stop_name: String,
+    pub calls: Vec<ScheduledCall>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduledCall {
+    // What GTFS-Realtime updates refer to, missing from older imports
+    #[serde(default)]
+    pub trip_id: String,
+    #[serde(default)]
+    pub gtfs_stop_id: String,
+    #[serde(default)]
+    pub stop_sequence: u32,
+    // What alerts refer to, `route` is the number people know it by
+    #[serde(default)]
+    pub route_id: String,
+    pub route: String,
+    pub mode: Option<TransportMode>,
+    pub headsign: String,
+    pub platform: Option<String>,
+    pub service_id: String,
+    // From noon minus 12 hours on the service day, can go past 24 hours
+    pub departure_seconds: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Service {
+    // Monday first
+    days: [bool; 7],
+    start: NaiveDate,
+    end: NaiveDate,
+    added: Vec<NaiveDate>,
+    removed: Vec<NaiveDate>,
+}
+
+impl Service {
+    fn runs_on(&self, date: NaiveDate) -> bool {
+        if self.removed.contains(&date) {
+            return false;
+        }
+
+        if self.added.contains(&date) {
+            return true;
+        }
+
+        date >= self.start
+            && date <= self.end
+            && self.days[date.weekday().num_days_from_monday() as usize]
+    }
+}
+
+// The text of each file in the feed, routes.txt is optional
+pub struct Feed {
+    pub stops: String,
+    pub trips: String,
+    pub stop_times: String,
+    pub calendar: String,
+    pub calendar_dates: String,
+    pub routes: Option<String>,
+}
+
+impl Timetable {
+    pub async fn load() -> Option<Self> {
+        match idb::get(STORAGE_KEY).await {
+            Ok(Some(timetable)) => return Some(timetable),
+            Ok(None) => {}
+            Err(error) => {
+                log!(format!("Could not load timetable: {error}"));
+                return None;
+            }
+        }
+
+        // Used to be kept in LocalStorage, move it over to free up the space
+        let timetable = LocalStorage::get::<Timetable>(STORAGE_KEY).ok()?;
+        match idb::set(STORAGE_KEY, &timetable).await {
+            Ok(()) => LocalStorage::delete(STORAGE_KEY),
+            Err(error) => log!(format!("Could not move timetable to IndexedDB: {error}")),
+        }
+        Some(timetable)
+    }
+
+    pub async fn save(&self) -> Result<(), String> {
+        idb::set(STORAGE_KEY, self).await
+    }
+
+    pub async fn clear() -> Result<(), String> {
+        LocalStorage::delete(STORAGE_KEY);
+        idb::delete(STORAGE_KEY).await
+    }
+
+    pub fn call_count(&self) -> usize {
+        self.stops.iter().map(|stop| stop.calls.len()).sum()
+    }
+
+    pub fn build(feed: &Feed, saved: &[BusStop]) -> Self {
+        let wanted = saved
+            .iter()
+            .map(|stop| stop.id.as_str())
+            .collect::<HashSet<_>>();
+
+        // GTFS stop id -> (saved stop id, platform)
+        let mut platforms = HashMap::new();
+        let mut names = HashMap::new();
+        let mut places = Vec::new();
+
+        let table = Table::new(&feed.stops);
+        let (stop_id, stop_name, parent, platform_code) = (
+            table.column("stop_id"),
+            table.column("stop_name"),
+            table.column("parent_station"),
+            table.column("platform_code"),
+        );
+
+        for row in table {
+            let id = get(&row, stop_id);
+            let platform = Some(get(&row, platform_code).to_string()).filter(|p| !p.is_empty());
+
+            // Platforms and entrances all have a parent
+            if get(&row, parent).is_empty() {
+                places.push(FeedStop {
+                    id: id.to_string(),
+                    name: get(&row, stop_name).to_string(),
+                });
+            }
+
+            if wanted.contains(id) {
+                names.insert(id.to_string(), get(&row, stop_name).to_string());
+                platforms.insert(id.to_string(), (id.to_string(), platform));
+            } else if wanted.contains(get(&row, parent)) {
+                platforms.insert(id.to_string(), (get(&row, parent).to_string(), platform));
+            }
+        }
+
+        // (trip id, GTFS stop id, stop sequence, saved stop id, platform, departure)
+        let mut stop_calls = Vec::new();
+
+        let table = Table::new(&feed.stop_times);
+        let (trip_id, stop_id, stop_sequence, departure_time) = (
+            table.column("trip_id"),
+            table.column("stop_id"),
+            table.column("stop_sequence"),
+            table.column("departure_time"),
+        );
+
+        for row in table {
+            let Some((saved_id, platform)) = platforms.get(get(&row, stop_id)) else {
+                continue;
+            };
+            let Some(seconds) = parse_time(get(&row, departure_time)) else {
+                continue;
+            };
+
+            stop_calls.push((
+                get(&row, trip_id).to_string(),
+                get(&row, stop_id).to_string(),
+                get(&row, stop_sequence).parse().unwrap_or_default(),
+                saved_id.clone(),
+                platform.clone(),
+                seconds,
+            ));
+        }
+
+        let needed_trips = stop_calls
+            .iter()
+            .map(|(trip_id, ..)| trip_id.as_str())
+            .collect::<HashSet<_>>();
+
+        // trip id -> (route id, service id, headsign)
+        let mut trips = HashMap::new();
+
+        let table = Table::new(&feed.trips);
+        let (trip_id, route_id, service_id, headsign) = (
+            table.column("trip_id"),
+            table.column("route_id"),
+            table.column("service_id"),
+            table.column("trip_headsign"),
+        );
+
+        for row in table {
+            if needed_trips.contains(get(&row, trip_id)) {
+                trips.insert(
+                    get(&row, trip_id).to_string(),
+                    (
+                        get(&row, route_id).to_string(),
+                        get(&row, service_id).to_string(),
+                        get(&row, headsign).to_string(),
+                    ),
+                );
+            }
+        }
+
+        // route id -> (number, mode)
+        let mut routes = HashMap::new();
+
+        if let Some(text) = &feed.routes {
+            let table = Table::new(text);
+            let (route_id, short_name, route_type) = (
+                table.column("route_id"),
+                table.column("route_short_name"),
+                table.column("route_type"),
+            );
+
+            for row in table {
+                routes.insert(
+                    get(&row, route_id).to_string(),
+                    (
+                        get(&row, short_name).to_string(),
+                        get(&row, route_type)
+                            .parse()
+                            .ok()
+                            .and_then(mode_from_route_type),
+                    ),
+                );
+            }
+        }
+
+        let needed_services = trips
+            .values()
+            .map(|(_, service_id, _)| service_id.as_str())
+            .collect::<HashSet<_>>();
+
+        let mut services = HashMap::new();
+
+        let table = Table::new(&feed.calendar);
+        let service_id = table.column("service_id");
+        let days = [
+            "monday",
+            "tuesday",
+            "wednesday",
+            "thursday",
+            "friday",
+            "saturday",
+            "sunday",
+        ]
+        .map(|day| table.column(day));
+        let (start_date, end_date) = (table.column("start_date"), table.column("end_date"));
+
+        for row in table {
+            let id = get(&row, service_id);
+            if !needed_services.contains(id) {
+                continue;
+            }
+
+            let (Some(start), Some(end)) = (
+                parse_date(get(&row, start_date)),
+                parse_date(get(&row, end_date)),
+            ) else {
+                continue;
+            };
+
+            services.insert(
+                id.to_string(),
+                Service {
+                    days: days.map(|day| get(&row, day) == "1"),
+                    start,
+                    end,
+                    added: Vec::new(),
+                    removed: Vec::new(),
+                },
+            );
+        }
+
+        let table = Table::new(&feed.calendar_dates);
+        let (service_id, date, exception_type) = (
+            table.column("service_id"),
+            table.column("date"),
+            table.column("exception_type"),
+        );
+
+        for row in table {
+            let id = get(&row, service_id);
+            let Some(date) = parse_date(get(&row, date)) else {
+                continue;
+            };
+            if !needed_services.contains(id) {
+                continue;
+            }
+
+            // Some feeds only use calendar_dates, those services never run otherwise
+            let service = services.entry(id.to_string()).or_insert(Service {
+                days: [false; 7],
+                start: date,
+                end: date,
+                added: Vec::new(),
+                removed: Vec::new(),
+            });
+
+            match get(&row, exception_type) {
+                "1" => service.added.push(date),
+                "2" => service.removed.push(date),
+                _ => {}
+            }
+        }
+
+        let stops = saved
+            .iter()
+            .map(|stop| {
+                let mut calls = stop_calls
+                    .iter()
+                    .filter(|(_, _, _, saved_id, ..)| *saved_id == stop.id)
+                    .filter_map(
+                        |(trip_id, gtfs_stop_id, stop_sequence, _, platform, seconds)| {
+                            let (route_id, service_id, headsign) = trips.get(trip_id)?;
+                            let (number, mode) = routes
+                                .get(route_id)
+                                .cloned()
+                                .unwrap_or((route_id.clone(), None));
+
+                            Some(ScheduledCall {
+                                trip_id: trip_id.clone(),
+                                gtfs_stop_id: gtfs_stop_id.clone(),
+                                stop_sequence: *stop_sequence,
+                                route_id: route_id.clone(),
+                                route: number,
+                                mode,
+                                headsign: headsign.clone(),
+                                platform: platform.clone(),
+                                service_id: service_id.clone(),
+                                departure_seconds: *seconds,
+                            })
+                        },
+                    )
+                    .collect::<Vec<_>>();
+                calls.sort_by_key(|call| call.departure_seconds);
+
+                StopTimetable {
+                    stop_id: stop.id.clone(),
+                    stop_name: names.get(&stop.id).cloned().unwrap_or(stop.name.clone()),
+                    calls,
+                }
+            })
+            .collect();
+
+        Timetable {
+            stops,
+            services,
+            places,
+        }
+    }
+
+    // Stops in the feed going by that name, or with exactly that id
+    pub fn search(&self, query: &str, limit: usize) -> Vec<BusStop> {
+        let query = query.trim();
+        if query.is_empty() {
+            return Vec::new();
+        }
+
+        let name = query.to_lowercase();
+        self.places
+            .iter()
+            .filter(|place| place.id == query || place.name.to_lowercase().contains(&name))
+            .take(limit)
+            .map(|place| BusStop {
+                id: place.id.clone(),
+                name: place.name.clone(),
+                // Nothing says what stops there until the timetable is imported again
+                modes: TransportMode::ALL.to_vec(),
+                walking_minutes: 0,
+            })
+            .collect()
+    }
+
+    // Scheduled departures from the stop from now on, as if they came from the API
+    pub fn departures(&self, stop: &BusStop, now: DateTime<Local>, limit: usize) -> Vec<Departure> {
+        self.calls(stop, now)
+            .into_iter()
+            .take(limit)
+            .map(|(call, time)| call.departure(stop, &self.stop_name(stop), time))
+            .collect()
+    }
+
+    // Every call at the stop from `from` on, with when it's due, soonest first
+    pub fn calls(
+        &self,
+        stop: &BusStop,
+        from: DateTime<Local>,
+    ) -> Vec<(&ScheduledCall, DateTime<Local>)> {
+        let Some(timetable) = self
+            .stops
+            .iter()
+            .find(|timetable| timetable.stop_id == stop.id)
+        else {
+            return Vec::new();
+        };
+
+        let today = from.date_naive();
+
+        // Yesterday's service day can still be running after midnight
+        let mut calls = [today - Duration::days(1), today, today + Duration::days(1)]
+            .into_iter()
+            .flat_map(|day| {
+                timetable
+                    .calls
+                    .iter()
+                    .filter(move |call| {
+                        self.services
+                            .get(&call.service_id)
+                            .is_some_and(|service| service.runs_on(day))
+                    })
+                    .map(move |call| (day, call))
+            })
+            .filter(|(_, call)| call.mode.is_none_or(|mode| stop.modes.contains(&mode)))
+            .filter_map(|(day, call)| {
+                let time =
+                    service_start(&Local, day)? + Duration::seconds(call.departure_seconds.into());
+
+                (time >= from).then_some((call, time))
+            })
+            .collect::<Vec<_>>();
+
+        calls.sort_by_key(|(_, time)| *time);
+        calls
+    }
+
+    // Everything alerts could name to mean this stop: (stop ids, route id -> route number)
+    pub fn affected_by<'a>(
+        &'a self,
+        stop: &'a BusStop,
+    ) -> (HashSet<&'a str>, HashMap<&'a str, &'a str>) {
+        let calls = self
+            .stops
+            .iter()
+            .filter(|timetable| timetable.stop_id == stop.id)
+            .flat_map(|timetable| &timetable.calls);
+
+        let mut stop_ids = HashSet::from([stop.id.as_str()]);
+        let mut routes = HashMap::new();
+        for call in calls {
+            stop_ids.insert(call.gtfs_stop_id.as_str());
+            routes.insert(call.route_id.as_str(), call.route.as_str());
+        }
+
+        (stop_ids, routes)
+    }
+
+    pub fn stop_name(&self, stop: &BusStop) -> String {
+        self.stops
+            .iter()
+            .find(|timetable| timetable.stop_id == stop.id)
+            .map(|timetable| timetable.stop_name.clone())
+            .unwrap_or(stop.name.clone())
+    }
+}
+
+impl ScheduledCall {
+    pub fn departure(&self, stop: &BusStop, stop_name: &str, time: DateTime<Local>) -> Departure {
+        Departure {
+            stop_id: stop.id.clone(),
+            number: self.route.clone(),
+            mode: self.mode,
+            stop_name: stop_name.to_string(),
+            destination: self.headsign.clone(),
+            platform: self.platform.clone(),
+            planned_time: time.fixed_offset(),
+            is_scheduled: true,
+            ..Default::default()
+        }
+    }
+}
+
+// GTFS counts from noon minus 12 hours, not midnight, so DST days still line up
+fn service_start<Tz: TimeZone>(timezone: &Tz, day: NaiveDate) -> Option<DateTime<Tz>> {
+    let noon = timezone
+        .from_local_datetime(&day.and_hms_opt(12, 0, 0)?)
+        .earliest()?;
+    Some(noon - Duration::hours(12))
+}
+
+// Expects the feed unzipped somewhere it can be fetched from, e.g. next to the app
+pub async fn import(base_url: &str, saved: &[BusStop]) -> Result<Timetable, FetchError> {
+    let base_url = base_url.trim().trim_end_matches('/');
+
+    // stop_times.txt can be tens of megabytes
+    let options = FetchOptions {
+        timeout: std::time::Duration::from_secs(120),
+        ..Default::default()
+    };
+
+    let file = |name: &str| fetch_text_with(format!("{base_url}/{name}"), &options);
+
+    let feed = Feed {
+        stops: file("stops.txt").await?,
+        trips: file("trips.txt").await?,
+        stop_times: file("stop_times.txt").await?,
+        calendar: file("calendar.txt").await?,
+        calendar_dates: file("calendar_dates.txt").await?,
+        // Route ids are used as the number without it
+        routes: file("routes.txt").await.ok(),
+    };
+
+    Ok(Timetable::build(&feed, saved))
+}
+
+// https://developers.google.com/transit/gtfs/reference#routestxt
+fn mode_from_route_type(route_type: u32) -> Option<TransportMode> {
+    match route_type {
+        0 | 900..=999 => Some(TransportMode::LightRail),
+        1 | 400..=499 => Some(TransportMode::Metro),
+        2 | 100..=199 => Some(TransportMode::Train),
+        3 | 700..=711 | 713..=799 => Some(TransportMode::Bus),
+        712 => Some(TransportMode::SchoolBus),
+        200..=299 => Some(TransportMode::Coach),
+        4 | 1000..=1099 | 1200..=1299 => Some(TransportMode::Ferry),
+        _ => None,
+    }
+}
+
+// HH:MM:SS, hours can go past 24 for trips that run after midnight
+fn parse_time(value: &str) -> Option<u32> {
+    let mut parts = value.trim().split(':').map(|part| part.parse::<u32>().ok());
+    let (hours, minutes, seconds) = (parts.next()??, parts.next()??, parts.next()??);
+    Some(hours * 3600 + minutes * 60 + seconds)
+}
+
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value.trim(), "%Y%m%d").ok()
+}
+
+fn get(row: &[String], column: Option<usize>) -> &str {
+    column
+        .and_then(|column| row.get(column))
+        .map(String::as_str)
+        .unwrap_or_default()
+}
+
+// A CSV file read a line at a time, so stop_times.txt never has to be split up all at once
+struct Table<'a> {
+    columns: Vec<String>,
+    lines: Lines<'a>,
+}
+
+impl<'a> Table<'a> {
+    fn new(text: &'a str) -> Self {
+        let mut lines = text.lines();
+        let columns = lines
+            .next()
+            .map(|header| split_row(header.trim_start_matches('\u{feff}')))
+            .unwrap_or_default();
+
+        Table { columns, lines }
+    }
+
+    fn column(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|column| column.trim() == name)
+    }
+}
+
+impl Iterator for Table<'_> {
+    type Item = Vec<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = self.lines.next()?;
+            if !line.trim().is_empty() {
+                return Some(split_row(line));
+            }
+        }
+    }
+}
+
+// Quoted fields can hold commas and doubled up quotes, but not line breaks
+fn split_row(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.trim_end_matches('\r').chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (c, in_quotes) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            ('"', _) => in_quotes = !in_quotes,
+            (',', false) => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+
+    fields.push(field);
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{FixedOffset, LocalResult, NaiveDateTime};
+
+    use super::*;
+
+    fn timetable() -> Timetable {
+        let feed = Feed {
+            stops: [
+                "stop_id,stop_name,parent_station,platform_code",
+                "200060,Central Station,,",
+                "2000331,Central Station Platform 1,200060,1",
+                "2000417,\"Railway Square, Stand D\",,",
+                "2077123,Hornsby Station,,",
+            ]
+            .join("\n"),
+            trips: String::new(),
+            stop_times: String::new(),
+            calendar: String::new(),
+            calendar_dates: String::new(),
+            routes: None,
+        };
+
+        Timetable::build(&feed, &[])
+    }
+
+    fn ids(stops: Vec<BusStop>) -> Vec<String> {
+        stops.into_iter().map(|stop| stop.id).collect()
+    }
+
+    #[test]
+    fn searches_stations_by_name() {
+        assert_eq!(
+            ids(timetable().search("central", 10)),
+            vec!["200060".to_string()]
+        );
+        assert_eq!(
+            ids(timetable().search(" SQUARE ", 10)),
+            vec!["2000417".to_string()]
+        );
+    }
+
+    #[test]
+    fn searches_by_id() {
+        assert_eq!(
+            ids(timetable().search("2077123", 10)),
+            vec!["2077123".to_string()]
+        );
+    }
+
+    #[test]
+    fn limits_search_results() {
+        assert_eq!(timetable().search("station", 1).len(), 1);
+        assert!(timetable().search("  ", 10).is_empty());
+    }
+
+    fn stop() -> BusStop {
+        BusStop {
+            id: "S1".to_string(),
+            name: "Central".to_string(),
+            modes: TransportMode::ALL.to_vec(),
+            walking_minutes: 0,
+        }
+    }
+
+    // Weekdays in June 2024, plus a Sunday special that's only in calendar_dates
+    fn june() -> Timetable {
+        let feed = Feed {
+            stops: "stop_id,stop_name\nS1,Central".to_string(),
+            trips: [
+                "route_id,service_id,trip_id,trip_headsign",
+                "R1,WEEKDAY,MORNING,Hornsby",
+                "R1,WEEKDAY,LATE,Hornsby",
+                "R1,SPECIAL,SUNDAY,Hornsby",
+            ]
+            .join("\n"),
+            stop_times: [
+                "trip_id,stop_id,stop_sequence,departure_time",
+                "MORNING,S1,1,08:00:00",
+                "LATE,S1,1,25:30:00",
+                "SUNDAY,S1,1,10:15:00",
+            ]
+            .join("\n"),
+            calendar: [
+                "service_id,monday,tuesday,wednesday,thursday,friday,saturday,sunday,start_date,end_date",
+                "WEEKDAY,1,1,1,1,1,0,0,20240601,20240630",
+            ]
+            .join("\n"),
+            calendar_dates: [
+                "service_id,date,exception_type",
+                // The King's Birthday public holiday
+                "WEEKDAY,20240610,2",
+                // Running on a Saturday for once
+                "WEEKDAY,20240608,1",
+                "SPECIAL,20240609,1",
+            ]
+            .join("\n"),
+            routes: None,
+        };
+
+        Timetable::build(&feed, &[stop()])
+    }
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 6, day).unwrap()
+    }
+
+    fn at(day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(2024, 6, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    fn first_call(timetable: &Timetable, from: DateTime<Local>) -> (String, DateTime<Local>) {
+        let (call, time) = timetable.calls(&stop(), from)[0];
+        (call.trip_id.clone(), time)
+    }
+
+    #[test]
+    fn runs_on_the_calendar_days() {
+        let timetable = june();
+        let weekday = &timetable.services["WEEKDAY"];
+
+        assert!(weekday.runs_on(date(7)));
+        assert!(weekday.runs_on(date(11)));
+        assert!(!weekday.runs_on(date(9)));
+        assert!(!weekday.runs_on(NaiveDate::from_ymd_opt(2024, 7, 1).unwrap()));
+    }
+
+    #[test]
+    fn applies_calendar_date_exceptions() {
+        let timetable = june();
+        let weekday = &timetable.services["WEEKDAY"];
+        let special = &timetable.services["SPECIAL"];
+
+        assert!(!weekday.runs_on(date(10)));
+        assert!(weekday.runs_on(date(8)));
+        assert!(special.runs_on(date(9)));
+        assert!(!special.runs_on(date(16)));
+    }
+
+    #[test]
+    fn follows_exceptions_into_the_calls() {
+        // Nothing on the holiday, not even the one just after midnight
+        assert_eq!(
+            first_call(&june(), at(10, 0, 0)),
+            ("MORNING".to_string(), at(11, 8, 0))
+        );
+        // Saturday's added, so its late one is still out on Sunday morning
+        assert_eq!(
+            first_call(&june(), at(9, 0, 0)),
+            ("LATE".to_string(), at(9, 1, 30))
+        );
+        assert_eq!(
+            first_call(&june(), at(9, 2, 0)),
+            ("SUNDAY".to_string(), at(9, 10, 15))
+        );
+    }
+
+    #[test]
+    fn runs_past_midnight_on_the_service_day() {
+        // 25:30 on Tuesday's service is half past one on Wednesday morning
+        assert_eq!(
+            first_call(&june(), at(12, 0, 0)),
+            ("LATE".to_string(), at(12, 1, 30))
+        );
+        assert_eq!(parse_time("25:30:00"), Some(25 * 3600 + 30 * 60));
+    }
+
+    // Sydney around the end of daylight saving in 2024, when 03:00 on the 7th of April went
+    // back to 02:00. Enough of a time zone to test with, without pulling one in.
+    #[derive(Debug, Clone, Copy)]
+    struct Sydney;
+
+    impl Sydney {
+        fn offsets() -> (FixedOffset, FixedOffset) {
+            (
+                FixedOffset::east_opt(11 * 3600).unwrap(),
+                FixedOffset::east_opt(10 * 3600).unwrap(),
+            )
+        }
+    }
+
+    impl TimeZone for Sydney {
+        type Offset = FixedOffset;
+
+        fn from_offset(_: &FixedOffset) -> Self {
+            Sydney
+        }
+
+        fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<FixedOffset> {
+            self.offset_from_local_datetime(&local.and_hms_opt(0, 0, 0).unwrap())
+        }
+
+        fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+            let (daylight, standard) = Sydney::offsets();
+            let fits = |offset| self.offset_from_utc_datetime(&(*local - offset)) == offset;
+
+            match (fits(daylight), fits(standard)) {
+                (true, true) => LocalResult::Ambiguous(daylight, standard),
+                (true, false) => LocalResult::Single(daylight),
+                (false, true) => LocalResult::Single(standard),
+                (false, false) => LocalResult::None,
+            }
+        }
+
+        fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+            self.offset_from_utc_datetime(&utc.and_hms_opt(0, 0, 0).unwrap())
+        }
+
+        fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+            let (daylight, standard) = Sydney::offsets();
+            let change = NaiveDate::from_ymd_opt(2024, 4, 6)
+                .unwrap()
+                .and_hms_opt(16, 0, 0)
+                .unwrap();
+
+            match *utc < change {
+                true => daylight,
+                false => standard,
+            }
+        }
+    }
+
+    #[test]
+    fn counts_from_noon_on_daylight_saving_days() {
+        let day = NaiveDate::from_ymd_opt(2024, 4, 7).unwrap();
+        let eight = service_start(&Sydney, day).unwrap() + Duration::hours(8);
+
+        // Counting from midnight would have it at 07:00, the day is 25 hours long
+        assert_eq!(eight, Sydney.with_ymd_and_hms(2024, 4, 7, 8, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn counts_from_midnight_on_other_days() {
+        let day = NaiveDate::from_ymd_opt(2024, 4, 8).unwrap();
+
+        assert_eq!(
+            service_start(&Sydney, day).unwrap(),
+            Sydney.with_ymd_and_hms(2024, 4, 8, 0, 0, 0).unwrap()
+        );
+    }
+}
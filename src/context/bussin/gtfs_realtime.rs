@@ -0,0 +1,918 @@
+use chrono::{DateTime, Duration, Local, TimeZone};
+use futures::future::{ready, LocalBoxFuture};
+use std::rc::Rc;
+
+use crate::{
+    context::bussin::{gtfs::Timetable, source::DepartureSource, BusStop, Departure, ServiceAlert},
+    utils::{fetch_bytes_with, FetchError, FetchOptions},
+};
+
+// Just enough of the GTFS-Realtime protobuf to read trip updates and alerts.
+// https://gtfs.org/documentation/realtime/proto/
+// Fields that aren't needed are skipped over, so newer feeds still decode.
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FeedMessage {
+    pub trip_updates: Vec<TripUpdate>,
+    pub alerts: Vec<Alert>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TripUpdate {
+    pub trip_id: String,
+    pub route_id: String,
+    pub is_cancelled: bool,
+    pub stop_time_updates: Vec<StopTimeUpdate>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct StopTimeUpdate {
+    pub stop_sequence: Option<u32>,
+    pub stop_id: Option<String>,
+    pub departure: Option<StopTimeEvent>,
+    pub arrival: Option<StopTimeEvent>,
+    pub is_skipped: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct StopTimeEvent {
+    // Seconds late, negative when early
+    pub delay: Option<i32>,
+    // Unix time
+    pub time: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Alert {
+    pub id: String,
+    // Unix times, either end can be open
+    pub active_periods: Vec<(Option<u64>, Option<u64>)>,
+    pub route_ids: Vec<String>,
+    pub stop_ids: Vec<String>,
+    pub effect: Option<u64>,
+    pub header: String,
+    pub description: String,
+}
+
+impl StopTimeUpdate {
+    // Departure is what matters at a stop, arrival is the next best thing
+    pub fn event(&self) -> Option<&StopTimeEvent> {
+        self.departure.as_ref().or(self.arrival.as_ref())
+    }
+}
+
+impl Alert {
+    pub fn is_active(&self, now: i64) -> bool {
+        self.active_periods.is_empty()
+            || self.active_periods.iter().any(|(start, end)| {
+                start.is_none_or(|start| start as i64 <= now)
+                    && end.is_none_or(|end| now <= end as i64)
+            })
+    }
+}
+
+// Per stop, the timetable would otherwise hand over the next couple of days
+const MAX_DEPARTURES: usize = 20;
+
+// A suburb's name can match a lot of stops
+const MAX_SEARCH_RESULTS: usize = 20;
+
+// How far back to look for services that were due already but are running late
+fn late_window() -> Duration {
+    Duration::minutes(30)
+}
+
+pub struct GtfsRealtime {
+    pub trip_updates_url: String,
+    // Optional, lots of agencies publish alerts as a separate feed
+    pub alerts_url: String,
+    pub api_key: String,
+    pub timetable: Option<Rc<Timetable>>,
+}
+
+impl GtfsRealtime {
+    fn options(&self) -> FetchOptions {
+        FetchOptions {
+            // Transport for NSW's open data wants it this way, most others ignore it
+            headers: match self.api_key.is_empty() {
+                true => Vec::new(),
+                false => vec![(
+                    "Authorization".to_string(),
+                    format!("apikey {}", self.api_key),
+                )],
+            },
+            ..Default::default()
+        }
+    }
+
+    async fn load(
+        &self,
+        stops: &[BusStop],
+        now: DateTime<Local>,
+    ) -> Vec<Result<Vec<Departure>, FetchError>> {
+        // The feed covers every stop, so it's only fetched the once
+        let feed = match fetch_bytes_with(self.trip_updates_url.clone(), &self.options()).await {
+            Ok(bytes) => decode(&bytes).map_err(FetchError::Decode),
+            Err(error) => Err(error),
+        };
+
+        let timetable = self.timetable.clone().ok_or(FetchError::Config(
+            "import a timetable for GTFS-Realtime".to_string(),
+        ));
+
+        stops
+            .iter()
+            .map(|stop| {
+                let feed = feed.clone()?;
+                let timetable = timetable.clone()?;
+                Ok(join(&timetable, &feed.trip_updates, stop, now))
+            })
+            .collect()
+    }
+}
+
+impl DepartureSource for GtfsRealtime {
+    fn departures<'a>(
+        &'a self,
+        stops: &'a [BusStop],
+        now: DateTime<Local>,
+    ) -> LocalBoxFuture<'a, Vec<Result<Vec<Departure>, FetchError>>> {
+        Box::pin(self.load(stops, now))
+    }
+
+    fn alerts<'a>(
+        &'a self,
+        stops: &'a [BusStop],
+        now: DateTime<Local>,
+    ) -> LocalBoxFuture<'a, Result<Vec<ServiceAlert>, FetchError>> {
+        Box::pin(async move {
+            if self.alerts_url.is_empty() {
+                return Ok(Vec::new());
+            }
+
+            let bytes = fetch_bytes_with(self.alerts_url.clone(), &self.options()).await?;
+            let feed = decode(&bytes).map_err(FetchError::Decode)?;
+            let timetable = self.timetable.clone().unwrap_or_default();
+
+            Ok(feed
+                .alerts
+                .iter()
+                .filter(|alert| alert.is_active(now.timestamp()))
+                .filter_map(|alert| affecting(alert, &timetable, stops))
+                .collect())
+        })
+    }
+
+    // GTFS-Realtime has no stop list of its own, the timetable's is the only one to hand
+    fn search_stops<'a>(
+        &'a self,
+        query: &'a str,
+    ) -> LocalBoxFuture<'a, Result<Vec<BusStop>, FetchError>> {
+        Box::pin(ready(match &self.timetable {
+            Some(timetable) => Ok(timetable.search(query, MAX_SEARCH_RESULTS)),
+            None => Err(FetchError::Config(
+                "import a timetable to search its stops".to_string(),
+            )),
+        }))
+    }
+}
+
+// Only the alerts that name one of the stops, or a route that goes through one
+fn affecting(alert: &Alert, timetable: &Timetable, stops: &[BusStop]) -> Option<ServiceAlert> {
+    let mut lines = Vec::new();
+    let mut is_affected = false;
+
+    for stop in stops {
+        let (stop_ids, routes) = timetable.affected_by(stop);
+
+        if alert
+            .stop_ids
+            .iter()
+            .any(|id| stop_ids.contains(id.as_str()))
+        {
+            is_affected = true;
+        }
+
+        for route_id in &alert.route_ids {
+            if let Some(number) = routes.get(route_id.as_str()) {
+                is_affected = true;
+                if !lines.contains(&number.to_string()) {
+                    lines.push(number.to_string());
+                }
+            }
+        }
+    }
+
+    is_affected.then(|| ServiceAlert {
+        id: alert.id.clone(),
+        title: alert.header.clone(),
+        description: alert.description.clone(),
+        lines,
+    })
+}
+
+// Lines the timetable up with whatever the trip updates say about it
+fn join(
+    timetable: &Timetable,
+    trip_updates: &[TripUpdate],
+    stop: &BusStop,
+    now: DateTime<Local>,
+) -> Vec<Departure> {
+    let stop_name = timetable.stop_name(stop);
+
+    timetable
+        .calls(stop, now - late_window())
+        .into_iter()
+        .map(|(call, time)| {
+            let mut departure = call.departure(stop, &stop_name, time);
+
+            let Some(update) = trip_updates
+                .iter()
+                .find(|update| update.trip_id == call.trip_id)
+            else {
+                return departure;
+            };
+
+            departure.is_scheduled = false;
+            departure.is_realtime = true;
+            departure.is_cancelled = update.is_cancelled;
+
+            // Updates carry forward to later stops until the next one says otherwise
+            let stop_update = update
+                .stop_time_updates
+                .iter()
+                .filter(|stop_update| {
+                    stop_update.stop_id.as_deref() == Some(call.gtfs_stop_id.as_str())
+                        || stop_update
+                            .stop_sequence
+                            .is_some_and(|sequence| sequence <= call.stop_sequence)
+                })
+                .max_by_key(|stop_update| stop_update.stop_sequence.unwrap_or(call.stop_sequence));
+
+            let Some(stop_update) = stop_update else {
+                return departure;
+            };
+
+            let is_this_stop = stop_update.stop_id.as_deref() == Some(call.gtfs_stop_id.as_str())
+                || stop_update.stop_sequence == Some(call.stop_sequence);
+
+            if is_this_stop && stop_update.is_skipped {
+                departure.is_cancelled = true;
+            }
+
+            let event = stop_update.event();
+            let estimated = match (is_this_stop, event.and_then(|event| event.time)) {
+                (true, Some(unix_time)) => Local.timestamp_opt(unix_time, 0).single(),
+                _ => event
+                    .and_then(|event| event.delay)
+                    .map(|delay| time + Duration::seconds(delay.into())),
+            };
+            departure.estimated_time = estimated.map(|estimated| estimated.fixed_offset());
+
+            departure
+        })
+        .filter(|departure| departure.departure_time() >= now)
+        .take(MAX_DEPARTURES)
+        .collect()
+}
+
+const CANCELED: u64 = 3;
+const SKIPPED: u64 = 1;
+
+pub fn decode(bytes: &[u8]) -> Result<FeedMessage, String> {
+    let mut feed = FeedMessage::default();
+
+    for field in Reader::new(bytes) {
+        if let (2, Value::Bytes(entity)) = field? {
+            decode_entity(entity, &mut feed)?;
+        }
+    }
+
+    Ok(feed)
+}
+
+fn decode_entity(bytes: &[u8], feed: &mut FeedMessage) -> Result<(), String> {
+    let mut id = String::new();
+    let mut is_deleted = false;
+    let mut trip_update = None;
+    let mut alert = None;
+
+    for field in Reader::new(bytes) {
+        match field? {
+            (1, Value::Bytes(value)) => id = string(value),
+            (2, Value::Varint(value)) => is_deleted = value != 0,
+            (3, Value::Bytes(value)) => trip_update = Some(decode_trip_update(value)?),
+            (5, Value::Bytes(value)) => alert = Some(decode_alert(value)?),
+            _ => {}
+        }
+    }
+
+    if is_deleted {
+        return Ok(());
+    }
+
+    feed.trip_updates.extend(trip_update);
+    feed.alerts.extend(alert.map(|alert| Alert { id, ..alert }));
+    Ok(())
+}
+
+fn decode_trip_update(bytes: &[u8]) -> Result<TripUpdate, String> {
+    let mut update = TripUpdate::default();
+
+    for field in Reader::new(bytes) {
+        match field? {
+            (1, Value::Bytes(trip)) => {
+                for field in Reader::new(trip) {
+                    match field? {
+                        (1, Value::Bytes(value)) => update.trip_id = string(value),
+                        (4, Value::Varint(value)) => update.is_cancelled = value == CANCELED,
+                        (5, Value::Bytes(value)) => update.route_id = string(value),
+                        _ => {}
+                    }
+                }
+            }
+            (2, Value::Bytes(value)) => update
+                .stop_time_updates
+                .push(decode_stop_time_update(value)?),
+            _ => {}
+        }
+    }
+
+    Ok(update)
+}
+
+fn decode_stop_time_update(bytes: &[u8]) -> Result<StopTimeUpdate, String> {
+    let mut update = StopTimeUpdate::default();
+
+    for field in Reader::new(bytes) {
+        match field? {
+            (1, Value::Varint(value)) => update.stop_sequence = Some(value as u32),
+            (2, Value::Bytes(value)) => update.arrival = Some(decode_stop_time_event(value)?),
+            (3, Value::Bytes(value)) => update.departure = Some(decode_stop_time_event(value)?),
+            (4, Value::Bytes(value)) => update.stop_id = Some(string(value)),
+            (5, Value::Varint(value)) => update.is_skipped = value == SKIPPED,
+            _ => {}
+        }
+    }
+
+    Ok(update)
+}
+
+fn decode_stop_time_event(bytes: &[u8]) -> Result<StopTimeEvent, String> {
+    let mut event = StopTimeEvent::default();
+
+    for field in Reader::new(bytes) {
+        match field? {
+            // Plain int32/int64, so negatives come through as huge varints
+            (1, Value::Varint(value)) => event.delay = Some(value as i64 as i32),
+            (2, Value::Varint(value)) => event.time = Some(value as i64),
+            _ => {}
+        }
+    }
+
+    Ok(event)
+}
+
+fn decode_alert(bytes: &[u8]) -> Result<Alert, String> {
+    let mut alert = Alert::default();
+
+    for field in Reader::new(bytes) {
+        match field? {
+            (1, Value::Bytes(period)) => {
+                let (mut start, mut end) = (None, None);
+                for field in Reader::new(period) {
+                    match field? {
+                        (1, Value::Varint(value)) => start = Some(value),
+                        (2, Value::Varint(value)) => end = Some(value),
+                        _ => {}
+                    }
+                }
+                alert.active_periods.push((start, end));
+            }
+            (5, Value::Bytes(entity)) => {
+                for field in Reader::new(entity) {
+                    match field? {
+                        (2, Value::Bytes(value)) => alert.route_ids.push(string(value)),
+                        (5, Value::Bytes(value)) => alert.stop_ids.push(string(value)),
+                        _ => {}
+                    }
+                }
+            }
+            (7, Value::Varint(value)) => alert.effect = Some(value),
+            (10, Value::Bytes(value)) => alert.header = translated(value)?,
+            (11, Value::Bytes(value)) => alert.description = translated(value)?,
+            _ => {}
+        }
+    }
+
+    Ok(alert)
+}
+
+// Picks English if it's there, otherwise whatever comes first
+fn translated(bytes: &[u8]) -> Result<String, String> {
+    let mut texts = Vec::new();
+
+    for field in Reader::new(bytes) {
+        if let (1, Value::Bytes(translation)) = field? {
+            let (mut text, mut language) = (String::new(), String::new());
+            for field in Reader::new(translation) {
+                match field? {
+                    (1, Value::Bytes(value)) => text = string(value),
+                    (2, Value::Bytes(value)) => language = string(value),
+                    _ => {}
+                }
+            }
+            texts.push((text, language));
+        }
+    }
+
+    let english = texts
+        .iter()
+        .position(|(_, language)| language.is_empty() || language.starts_with("en"));
+
+    Ok(english
+        .or((!texts.is_empty()).then_some(0))
+        .map(|i| texts.swap_remove(i).0)
+        .unwrap_or_default())
+}
+
+fn string(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+enum Value<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    Fixed,
+}
+
+// Walks the fields of one message, nested messages come out as bytes to decode in turn
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Reader { bytes, position: 0 }
+    }
+
+    fn varint(&mut self) -> Result<u64, String> {
+        let mut value = 0;
+
+        for shift in (0..64).step_by(7) {
+            let byte = *self
+                .bytes
+                .get(self.position)
+                .ok_or("varint runs past the end")?;
+            self.position += 1;
+
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        Err("varint too long".to_string())
+    }
+
+    fn take(&mut self, length: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .position
+            .checked_add(length)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or("field runs past the end")?;
+        let bytes = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn field(&mut self) -> Result<(u64, Value<'a>), String> {
+        let key = self.varint()?;
+
+        let value = match key & 0x7 {
+            0 => Value::Varint(self.varint()?),
+            1 => {
+                self.take(8)?;
+                Value::Fixed
+            }
+            2 => {
+                let length = self.varint()? as usize;
+                Value::Bytes(self.take(length)?)
+            }
+            5 => {
+                self.take(4)?;
+                Value::Fixed
+            }
+            wire_type => return Err(format!("unsupported wire type {wire_type}")),
+        };
+
+        Ok((key >> 3, value))
+    }
+}
+
+impl<'a> Iterator for Reader<'a> {
+    type Item = Result<(u64, Value<'a>), String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.position >= self.bytes.len() {
+            return None;
+        }
+
+        let field = self.field();
+        if field.is_err() {
+            // Nothing after a broken field can be trusted
+            self.position = self.bytes.len();
+        }
+        Some(field)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::bussin::{gtfs::Feed, TransportMode};
+
+    // Made up rather than recorded, `fixture::feed` below is what it's built from
+    const FEED: &[u8] = include_bytes!("../../../fixtures/gtfs_realtime.pb");
+
+    // Field numbers are from https://gtfs.org/documentation/realtime/proto/
+    mod fixture {
+        fn varint(mut value: u64) -> Vec<u8> {
+            let mut bytes = Vec::new();
+            while value >= 0x80 {
+                bytes.push(value as u8 | 0x80);
+                value >>= 7;
+            }
+            bytes.push(value as u8);
+            bytes
+        }
+
+        // Negative int32s go out as ten byte varints, same as protoc does it
+        fn int(field: u64, value: i64) -> Vec<u8> {
+            [varint(field << 3), varint(value as u64)].concat()
+        }
+
+        fn bytes(field: u64, value: &[u8]) -> Vec<u8> {
+            [
+                varint(field << 3 | 2),
+                varint(value.len() as u64),
+                value.to_vec(),
+            ]
+            .concat()
+        }
+
+        fn text(field: u64, value: &str) -> Vec<u8> {
+            bytes(field, value.as_bytes())
+        }
+
+        fn message(field: u64, fields: &[Vec<u8>]) -> Vec<u8> {
+            bytes(field, &fields.concat())
+        }
+
+        // TranslatedString, one (text, language) per translation
+        fn translated(field: u64, translations: &[(&str, &str)]) -> Vec<u8> {
+            let translations = translations
+                .iter()
+                .map(|(value, language)| message(1, &[text(1, value), text(2, language)]))
+                .collect::<Vec<_>>();
+            message(field, &translations)
+        }
+
+        pub fn feed() -> Vec<u8> {
+            let header = message(1, &[text(1, "2.0"), int(2, 0), int(3, 1717200000)]);
+
+            // Running late, two minutes at stop 5 and a minute early by stop 6
+            let delayed = message(
+                2,
+                &[
+                    text(1, "t1"),
+                    message(
+                        3,
+                        &[
+                            message(1, &[text(1, "T1"), text(5, "R1")]),
+                            message(2, &[int(1, 5), text(4, "S1"), message(3, &[int(1, 120)])]),
+                            message(2, &[int(1, 6), message(2, &[int(1, -60)])]),
+                            int(4, 1717200000),
+                        ],
+                    ),
+                ],
+            );
+
+            // The whole trip is CANCELED
+            let cancelled = message(
+                2,
+                &[
+                    text(1, "t2"),
+                    message(3, &[message(1, &[text(1, "T2"), int(4, 3), text(5, "R2")])]),
+                ],
+            );
+
+            // SKIPPED at stop 4, with an absolute time rather than a delay
+            let skipped = message(
+                2,
+                &[
+                    text(1, "t3"),
+                    message(
+                        3,
+                        &[
+                            message(1, &[text(1, "T3"), text(5, "R1")]),
+                            message(
+                                2,
+                                &[
+                                    int(1, 4),
+                                    text(4, "S1"),
+                                    int(5, 1),
+                                    message(3, &[int(2, 1717203600)]),
+                                ],
+                            ),
+                        ],
+                    ),
+                ],
+            );
+
+            let deleted = message(
+                2,
+                &[
+                    text(1, "t4"),
+                    int(2, 1),
+                    message(3, &[message(1, &[text(1, "T4")])]),
+                ],
+            );
+
+            // A day of SIGNIFICANT_DELAYS on route R1 and at stop S1, French first
+            let alert = message(
+                2,
+                &[
+                    text(1, "a1"),
+                    message(
+                        5,
+                        &[
+                            message(1, &[int(1, 1717200000), int(2, 1717286400)]),
+                            message(5, &[text(2, "R1")]),
+                            message(5, &[text(5, "S1")]),
+                            int(6, 1),
+                            int(7, 3),
+                            translated(
+                                10,
+                                &[
+                                    ("Des bus remplacent les trains", "fr"),
+                                    ("Buses replace trains", "en"),
+                                ],
+                            ),
+                            translated(11, &[("Between Central and Hornsby", "")]),
+                        ],
+                    ),
+                ],
+            );
+
+            [header, delayed, cancelled, skipped, deleted, alert].concat()
+        }
+    }
+
+    // UPDATE_FIXTURES=1 cargo test writes it out again after changing `fixture::feed`
+    #[test]
+    fn fixture_matches_its_source() {
+        let feed = fixture::feed();
+
+        if std::env::var_os("UPDATE_FIXTURES").is_some() {
+            let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/gtfs_realtime.pb");
+            std::fs::write(path, &feed).unwrap();
+            return;
+        }
+
+        assert_eq!(feed, FEED);
+    }
+
+    fn trip<'a>(feed: &'a FeedMessage, trip_id: &str) -> &'a TripUpdate {
+        feed.trip_updates
+            .iter()
+            .find(|update| update.trip_id == trip_id)
+            .unwrap()
+    }
+
+    #[test]
+    fn decodes_delays() {
+        let feed = decode(FEED).unwrap();
+        let update = trip(&feed, "T1");
+
+        assert_eq!(update.route_id, "R1");
+        assert!(!update.is_cancelled);
+        assert_eq!(update.stop_time_updates.len(), 2);
+
+        let departing = &update.stop_time_updates[0];
+        assert_eq!(departing.stop_sequence, Some(5));
+        assert_eq!(departing.stop_id.as_deref(), Some("S1"));
+        assert_eq!(departing.event().unwrap().delay, Some(120));
+
+        // Only has an arrival, and it's early
+        let arriving = &update.stop_time_updates[1];
+        assert_eq!(arriving.stop_id, None);
+        assert_eq!(arriving.event().unwrap().delay, Some(-60));
+    }
+
+    #[test]
+    fn decodes_cancellations() {
+        let feed = decode(FEED).unwrap();
+
+        assert!(trip(&feed, "T2").is_cancelled);
+
+        let skipped = &trip(&feed, "T3").stop_time_updates[0];
+        assert!(skipped.is_skipped);
+        assert_eq!(skipped.event().unwrap().time, Some(1717203600));
+    }
+
+    #[test]
+    fn leaves_out_deleted_entities() {
+        let feed = decode(FEED).unwrap();
+
+        assert_eq!(feed.trip_updates.len(), 3);
+        assert!(feed
+            .trip_updates
+            .iter()
+            .all(|update| update.trip_id != "T4"));
+    }
+
+    #[test]
+    fn decodes_alerts() {
+        let feed = decode(FEED).unwrap();
+        let alert = &feed.alerts[0];
+
+        assert_eq!(feed.alerts.len(), 1);
+        assert_eq!(alert.id, "a1");
+        // English over whichever translation comes first
+        assert_eq!(alert.header, "Buses replace trains");
+        assert_eq!(alert.description, "Between Central and Hornsby");
+        assert_eq!(alert.route_ids, vec!["R1"]);
+        assert_eq!(alert.stop_ids, vec!["S1"]);
+        assert_eq!(alert.effect, Some(3));
+        assert_eq!(
+            alert.active_periods,
+            vec![(Some(1717200000), Some(1717286400))]
+        );
+
+        assert!(alert.is_active(1717250000));
+        assert!(!alert.is_active(1717290000));
+    }
+
+    #[test]
+    fn rejects_truncated_feeds() {
+        assert!(decode(&FEED[..FEED.len() - 1]).is_err());
+
+        // Wherever it gets cut off, it shouldn't panic
+        for end in 0..FEED.len() {
+            let _ = decode(&FEED[..end]);
+        }
+    }
+
+    fn stop() -> BusStop {
+        BusStop {
+            id: "S1".to_string(),
+            name: "Central".to_string(),
+            modes: TransportMode::ALL.to_vec(),
+            walking_minutes: 0,
+        }
+    }
+
+    // Three trips through S1, only on Saturday the 1st of June so the next day's don't turn up
+    fn timetable() -> Timetable {
+        let feed = Feed {
+            stops: "stop_id,stop_name\nS0,Before\nS1,Central\nS2,After".to_string(),
+            trips: [
+                "route_id,service_id,trip_id,trip_headsign",
+                "R1,JUNE,T1,Hornsby",
+                "R1,JUNE,T2,Hornsby",
+                "R1,JUNE,T3,Hornsby",
+            ]
+            .join("\n"),
+            stop_times: [
+                "trip_id,stop_id,stop_sequence,departure_time",
+                "T1,S1,3,08:00:00",
+                "T2,S1,3,08:10:00",
+                "T3,S1,2,07:20:00",
+            ]
+            .join("\n"),
+            calendar: [
+                "service_id,monday,tuesday,wednesday,thursday,friday,saturday,sunday,start_date,end_date",
+                "JUNE,0,0,0,0,0,1,0,20240601,20240601",
+            ]
+            .join("\n"),
+            calendar_dates: String::new(),
+            routes: None,
+        };
+
+        Timetable::build(&feed, &[stop()])
+    }
+
+    fn at(hour: u32, minute: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 6, 1, hour, minute, 0).unwrap()
+    }
+
+    fn stop_update(sequence: u32, stop_id: Option<&str>, delay: i32) -> StopTimeUpdate {
+        StopTimeUpdate {
+            stop_sequence: Some(sequence),
+            stop_id: stop_id.map(str::to_string),
+            departure: Some(StopTimeEvent {
+                delay: Some(delay),
+                time: None,
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn trip_update(trip_id: &str, stop_time_updates: Vec<StopTimeUpdate>) -> TripUpdate {
+        TripUpdate {
+            trip_id: trip_id.to_string(),
+            route_id: "R1".to_string(),
+            is_cancelled: false,
+            stop_time_updates,
+        }
+    }
+
+    fn departure(departures: &[Departure], planned: DateTime<Local>) -> &Departure {
+        departures
+            .iter()
+            .find(|departure| departure.planned_time == planned)
+            .unwrap()
+    }
+
+    #[test]
+    fn carries_delays_forward_by_stop_sequence() {
+        let updates = [trip_update(
+            "T1",
+            vec![
+                stop_update(1, Some("S0"), 60),
+                stop_update(2, None, 180),
+                // Further along, shouldn't reach back to S1
+                stop_update(4, Some("S2"), 600),
+            ],
+        )];
+
+        let departures = join(&timetable(), &updates, &stop(), at(7, 0));
+        let departing = departure(&departures, at(8, 0));
+
+        assert!(departing.is_realtime);
+        assert!(!departing.is_scheduled);
+        assert_eq!(departing.delay_minutes(), Some(3));
+
+        // Nothing about T2, it's left as timetabled
+        let untracked = departure(&departures, at(8, 10));
+        assert!(untracked.is_scheduled);
+        assert_eq!(untracked.delay_minutes(), None);
+    }
+
+    #[test]
+    fn cancels_skipped_stops() {
+        let skipped = StopTimeUpdate {
+            is_skipped: true,
+            ..stop_update(3, Some("S1"), 0)
+        };
+        let skipped_earlier = StopTimeUpdate {
+            is_skipped: true,
+            ..stop_update(2, Some("S0"), 0)
+        };
+        let updates = [
+            trip_update("T1", vec![skipped]),
+            trip_update("T2", vec![skipped_earlier]),
+        ];
+
+        let departures = join(&timetable(), &updates, &stop(), at(7, 0));
+
+        assert!(departure(&departures, at(8, 0)).is_cancelled);
+        assert!(!departure(&departures, at(8, 10)).is_cancelled);
+    }
+
+    #[test]
+    fn cancels_cancelled_trips() {
+        let updates = [TripUpdate {
+            is_cancelled: true,
+            ..trip_update("T2", Vec::new())
+        }];
+
+        let departures = join(&timetable(), &updates, &stop(), at(7, 0));
+
+        assert!(departure(&departures, at(8, 10)).is_cancelled);
+        assert!(!departure(&departures, at(8, 0)).is_cancelled);
+    }
+
+    #[test]
+    fn keeps_late_running_services_within_the_window() {
+        let updates = [
+            // Due 08:00, now leaving 08:25
+            trip_update("T1", vec![stop_update(3, Some("S1"), 25 * 60)]),
+            // Due 08:10, now leaving 08:15, which has been and gone
+            trip_update("T2", vec![stop_update(3, Some("S1"), 5 * 60)]),
+            // Due 07:20, too long ago to look for even though it's 70 minutes late
+            trip_update("T3", vec![stop_update(2, Some("S1"), 70 * 60)]),
+        ];
+
+        let departures = join(&timetable(), &updates, &stop(), at(8, 20));
+        let planned = departures
+            .iter()
+            .map(|departure| departure.planned_time)
+            .collect::<Vec<_>>();
+
+        assert_eq!(planned, vec![at(8, 0)]);
+        assert_eq!(departures[0].delay_minutes(), Some(25));
+    }
+}
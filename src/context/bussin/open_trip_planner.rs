@@ -0,0 +1,262 @@
+use chrono::{DateTime, Local, TimeZone};
+use futures::future::{join_all, LocalBoxFuture};
+use serde_json::{json, Value};
+
+use crate::{
+    context::bussin::{
+        source::{arrived_alerts, DepartureSource},
+        BusStop, Departure, ServiceAlert, TransportMode,
+    },
+    utils::{post_json, FetchError},
+};
+
+// OpenTripPlanner 2's GTFS GraphQL API, e.g. https://otp.example.org/otp/gtfs/v1
+// Stop ids are the feed-prefixed ones it uses everywhere, like "1:200060".
+
+const MAX_DEPARTURES: usize = 20;
+
+const DEPARTURES_QUERY: &str = "
+query Departures($id: String!, $start: Long!, $count: Int!) {
+  stop(id: $id) {
+    name
+    stoptimesWithoutPatterns(startTime: $start, numberOfDepartures: $count, omitNonPickups: true) {
+      serviceDay
+      scheduledDeparture
+      realtimeDeparture
+      realtime
+      realtimeState
+      headsign
+      stop { platformCode }
+      trip { route { shortName mode } }
+    }
+  }
+}";
+
+const ALERTS_QUERY: &str = "
+query Alerts($id: String!) {
+  stop(id: $id) {
+    alerts { id alertHeaderText alertDescriptionText effectiveStartDate effectiveEndDate }
+    routes {
+      shortName
+      alerts { id alertHeaderText alertDescriptionText effectiveStartDate effectiveEndDate }
+    }
+  }
+}";
+
+const SEARCH_QUERY: &str = "
+query Search($name: String!) {
+  stops(name: $name) { gtfsId name vehicleMode }
+}";
+
+pub struct OpenTripPlanner {
+    pub graphql_url: String,
+}
+
+impl OpenTripPlanner {
+    async fn query(&self, query: &str, variables: Value) -> Result<Value, FetchError> {
+        let body = json!({ "query": query, "variables": variables });
+        let response = post_json::<Value>(self.graphql_url.clone(), &body).await?;
+
+        // GraphQL says what went wrong in the body, usually with a 200
+        match &response["errors"][0]["message"] {
+            Value::String(message) if response["data"].is_null() => {
+                Err(FetchError::Decode(message.clone()))
+            }
+            _ => Ok(response["data"].clone()),
+        }
+    }
+
+    async fn stop_departures(
+        &self,
+        stop: &BusStop,
+        now: DateTime<Local>,
+    ) -> Result<Vec<Departure>, FetchError> {
+        if stop.modes.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let variables = json!({ "id": stop.id, "start": now.timestamp(), "count": MAX_DEPARTURES });
+        let data = self.query(DEPARTURES_QUERY, variables).await?;
+
+        let stop_name = data["stop"]["name"]
+            .as_str()
+            .ok_or(FetchError::Decode(format!("no stop {}", stop.id)))?;
+        let stop_times = data["stop"]["stoptimesWithoutPatterns"]
+            .as_array()
+            .ok_or(FetchError::Decode("missing stoptimes".to_string()))?;
+
+        let departures = stop_times
+            .iter()
+            .filter_map(|stop_time| {
+                let service_day = stop_time["serviceDay"].as_i64()?;
+                let time = |key: &str| {
+                    let seconds = stop_time[key].as_i64()?;
+                    Local
+                        .timestamp_opt(service_day + seconds, 0)
+                        .single()
+                        .map(|time| time.fixed_offset())
+                };
+
+                let route = &stop_time["trip"]["route"];
+                let mode = route["mode"].as_str().and_then(mode_from_otp);
+                let is_realtime = stop_time["realtime"].as_bool().unwrap_or(false);
+
+                Some(Departure {
+                    stop_id: stop.id.clone(),
+                    number: route["shortName"].as_str().unwrap_or_default().to_string(),
+                    mode,
+                    stop_name: stop_name.to_string(),
+                    destination: stop_time["headsign"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .to_string(),
+                    platform: stop_time["stop"]["platformCode"]
+                        .as_str()
+                        .filter(|platform| !platform.is_empty())
+                        .map(str::to_string),
+                    planned_time: time("scheduledDeparture")?,
+                    estimated_time: is_realtime.then(|| time("realtimeDeparture")).flatten(),
+                    is_realtime,
+                    is_cancelled: stop_time["realtimeState"].as_str() == Some("CANCELED"),
+                    ..Default::default()
+                })
+            })
+            .filter(|departure| departure.mode.is_none_or(|mode| stop.modes.contains(&mode)))
+            .collect();
+
+        Ok(departures)
+    }
+
+    async fn stop_alerts(
+        &self,
+        stop: &BusStop,
+        now: DateTime<Local>,
+    ) -> Result<Vec<ServiceAlert>, FetchError> {
+        let data = self.query(ALERTS_QUERY, json!({ "id": stop.id })).await?;
+
+        let stop_alerts = data["stop"]["alerts"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|alert| (alert, None));
+
+        let route_alerts = data["stop"]["routes"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .flat_map(|route| {
+                let number = route["shortName"].as_str();
+                route["alerts"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .map(move |alert| (alert, number))
+            });
+
+        let alerts = stop_alerts
+            .chain(route_alerts)
+            .filter(|(alert, _)| {
+                alert["effectiveStartDate"]
+                    .as_i64()
+                    .is_none_or(|start| start <= now.timestamp())
+                    && alert["effectiveEndDate"]
+                        .as_i64()
+                        .is_none_or(|end| now.timestamp() <= end)
+            })
+            .filter_map(|(alert, number)| {
+                Some(ServiceAlert {
+                    id: alert["id"].as_str()?.to_string(),
+                    title: alert["alertHeaderText"].as_str()?.to_string(),
+                    description: alert["alertDescriptionText"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .to_string(),
+                    lines: number.map(str::to_string).into_iter().collect(),
+                })
+            })
+            .collect();
+
+        Ok(alerts)
+    }
+}
+
+impl DepartureSource for OpenTripPlanner {
+    fn departures<'a>(
+        &'a self,
+        stops: &'a [BusStop],
+        now: DateTime<Local>,
+    ) -> LocalBoxFuture<'a, Vec<Result<Vec<Departure>, FetchError>>> {
+        Box::pin(join_all(
+            stops.iter().map(|stop| self.stop_departures(stop, now)),
+        ))
+    }
+
+    fn alerts<'a>(
+        &'a self,
+        stops: &'a [BusStop],
+        now: DateTime<Local>,
+    ) -> LocalBoxFuture<'a, Result<Vec<ServiceAlert>, FetchError>> {
+        Box::pin(async move {
+            let results = join_all(stops.iter().map(|stop| self.stop_alerts(stop, now))).await;
+
+            // A route alert comes back once for every stop the route goes through
+            let mut alerts = Vec::<ServiceAlert>::new();
+            for alert in arrived_alerts(stops, results)?.into_iter().flatten() {
+                match alerts.iter_mut().find(|existing| existing.id == alert.id) {
+                    Some(existing) => {
+                        for line in alert.lines {
+                            if !existing.lines.contains(&line) {
+                                existing.lines.push(line);
+                            }
+                        }
+                    }
+                    None => alerts.push(alert),
+                }
+            }
+
+            Ok(alerts)
+        })
+    }
+
+    fn search_stops<'a>(
+        &'a self,
+        query: &'a str,
+    ) -> LocalBoxFuture<'a, Result<Vec<BusStop>, FetchError>> {
+        Box::pin(async move {
+            let data = self.query(SEARCH_QUERY, json!({ "name": query })).await?;
+
+            let stops = data["stops"]
+                .as_array()
+                .ok_or(FetchError::Decode("missing stops".to_string()))?
+                .iter()
+                .filter_map(|stop| {
+                    let modes = match stop["vehicleMode"].as_str().and_then(mode_from_otp) {
+                        Some(mode) => vec![mode],
+                        None => TransportMode::ALL.to_vec(),
+                    };
+
+                    Some(BusStop {
+                        id: stop["gtfsId"].as_str()?.to_string(),
+                        name: stop["name"].as_str()?.to_string(),
+                        modes,
+                        walking_minutes: 0,
+                    })
+                })
+                .collect();
+
+            Ok(stops)
+        })
+    }
+}
+
+fn mode_from_otp(mode: &str) -> Option<TransportMode> {
+    match mode {
+        "RAIL" => Some(TransportMode::Train),
+        "SUBWAY" => Some(TransportMode::Metro),
+        "TRAM" => Some(TransportMode::LightRail),
+        "BUS" | "TROLLEYBUS" => Some(TransportMode::Bus),
+        "COACH" => Some(TransportMode::Coach),
+        "FERRY" => Some(TransportMode::Ferry),
+        _ => None,
+    }
+}
@@ -0,0 +1,116 @@
+use chrono::{DateTime, Local};
+use futures::future::LocalBoxFuture;
+use gloo_console::log;
+use gloo_storage::{LocalStorage, Storage};
+use serde::{Deserialize, Serialize};
+use std::rc::Rc;
+
+use crate::{
+    context::bussin::{
+        gtfs::Timetable, gtfs_realtime::GtfsRealtime, open_trip_planner::OpenTripPlanner,
+        transport_nsw::TransportNsw, BusStop, Departure, ServiceAlert,
+    },
+    utils::FetchError,
+};
+
+const STORAGE_KEY: &str = "bus_source";
+
+// Where departures come from, one result per stop so one bad stop doesn't sink the rest
+pub trait DepartureSource {
+    fn departures<'a>(
+        &'a self,
+        stops: &'a [BusStop],
+        now: DateTime<Local>,
+    ) -> LocalBoxFuture<'a, Vec<Result<Vec<Departure>, FetchError>>>;
+
+    // Whatever's currently disrupting the stops or the routes through them
+    fn alerts<'a>(
+        &'a self,
+        stops: &'a [BusStop],
+        now: DateTime<Local>,
+    ) -> LocalBoxFuture<'a, Result<Vec<ServiceAlert>, FetchError>>;
+
+    // Stops to pick from when setting up, with whatever they're served by
+    fn search_stops<'a>(
+        &'a self,
+        query: &'a str,
+    ) -> LocalBoxFuture<'a, Result<Vec<BusStop>, FetchError>>;
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub enum SourceConfig {
+    #[default]
+    TransportNsw,
+    // Joined up with the imported timetable, which is where the trip ids come from
+    GtfsRealtime {
+        trip_updates_url: String,
+        #[serde(default)]
+        alerts_url: String,
+        #[serde(default)]
+        api_key: String,
+    },
+    // Any OpenTripPlanner 2 instance, which does its own GTFS-Realtime
+    OpenTripPlanner {
+        graphql_url: String,
+    },
+}
+
+impl SourceConfig {
+    pub fn load() -> Self {
+        LocalStorage::get(STORAGE_KEY).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Err(error) = LocalStorage::set(STORAGE_KEY, self) {
+            log!(format!("Could not save departure source: {error}"));
+        }
+    }
+
+    // GTFS-Realtime only has trip ids, the timetable is what says where they stop
+    pub fn source(&self, timetable: Option<Rc<Timetable>>) -> Box<dyn DepartureSource> {
+        match self {
+            SourceConfig::TransportNsw => Box::new(TransportNsw),
+            SourceConfig::GtfsRealtime {
+                trip_updates_url,
+                alerts_url,
+                api_key,
+            } => Box::new(GtfsRealtime {
+                trip_updates_url: trip_updates_url.clone(),
+                alerts_url: alerts_url.clone(),
+                api_key: api_key.clone(),
+                timetable,
+            }),
+            SourceConfig::OpenTripPlanner { graphql_url } => Box::new(OpenTripPlanner {
+                graphql_url: graphql_url.clone(),
+            }),
+        }
+    }
+}
+
+// Each stop's alerts are asked for separately, one failing shouldn't lose the
+// rest. Only an error when there was nothing to show for any of them.
+pub fn arrived_alerts(
+    stops: &[BusStop],
+    results: Vec<Result<Vec<ServiceAlert>, FetchError>>,
+) -> Result<Vec<Vec<ServiceAlert>>, FetchError> {
+    let mut arrived = Vec::new();
+    let mut error = None;
+
+    for (stop, result) in stops.iter().zip(results) {
+        match result {
+            Ok(alerts) => arrived.push(alerts),
+            Err(stop_error) => {
+                log!(format!(
+                    "Could not load alerts for {}: {stop_error}",
+                    stop.name
+                ));
+                error = Some(stop_error);
+            }
+        }
+    }
+
+    match (arrived.is_empty(), error) {
+        (true, Some(error)) => Err(error),
+        _ => Ok(arrived),
+    }
+}
@@ -0,0 +1,281 @@
+use chrono::{DateTime, Local};
+use futures::future::{join_all, LocalBoxFuture};
+use serde_json::Value;
+use web_sys::js_sys::encode_uri_component;
+
+use crate::{
+    context::bussin::{
+        default_modes,
+        source::{arrived_alerts, DepartureSource},
+        BusStop, Departure, Occupancy, ServiceAlert, TransportMode,
+    },
+    utils::{fetch, FetchError},
+};
+
+// The trip planner behind transportnsw.info. None of it is documented, so it's
+// all kept in here in case it changes shape.
+
+pub struct TransportNsw;
+
+impl DepartureSource for TransportNsw {
+    fn departures<'a>(
+        &'a self,
+        stops: &'a [BusStop],
+        _now: DateTime<Local>,
+    ) -> LocalBoxFuture<'a, Vec<Result<Vec<Departure>, FetchError>>> {
+        Box::pin(join_all(stops.iter().cloned().map(fetch_departures)))
+    }
+
+    fn alerts<'a>(
+        &'a self,
+        stops: &'a [BusStop],
+        now: DateTime<Local>,
+    ) -> LocalBoxFuture<'a, Result<Vec<ServiceAlert>, FetchError>> {
+        Box::pin(async move {
+            let results = join_all(stops.iter().map(|stop| fetch_alerts(stop.clone(), now))).await;
+
+            // The same track work shows up at every stop along the line
+            let mut alerts = Vec::<ServiceAlert>::new();
+            for alert in arrived_alerts(stops, results)?.into_iter().flatten() {
+                if !alerts.iter().any(|existing| existing.id == alert.id) {
+                    alerts.push(alert);
+                }
+            }
+
+            Ok(alerts)
+        })
+    }
+
+    fn search_stops<'a>(
+        &'a self,
+        query: &'a str,
+    ) -> LocalBoxFuture<'a, Result<Vec<BusStop>, FetchError>> {
+        Box::pin(search_stops(query.to_string()))
+    }
+}
+
+// What the trip planner calls them
+fn product_class(mode: TransportMode) -> u64 {
+    match mode {
+        TransportMode::Train => 1,
+        TransportMode::Metro => 2,
+        TransportMode::LightRail => 4,
+        TransportMode::Bus => 5,
+        TransportMode::Coach => 7,
+        TransportMode::Ferry => 9,
+        TransportMode::SchoolBus => 11,
+    }
+}
+
+fn mode_from_product_class(class: u64) -> Option<TransportMode> {
+    TransportMode::ALL
+        .into_iter()
+        .find(|mode| product_class(*mode) == class)
+}
+
+// https://transportnsw.info/api/trip/v1/departure-list-request?name=G12312312&type=stop&depArrMacro=dep&depType=stopEvents&excludedModes=2,9,11,1,4,7
+
+async fn fetch_departures(stop: BusStop) -> Result<Vec<Departure>, FetchError> {
+    // Nothing picked, nothing to show
+    if stop.modes.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let excluded_modes = TransportMode::ALL
+        .iter()
+        .filter(|mode| !stop.modes.contains(mode))
+        .map(|mode| product_class(*mode).to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+
+    let params = [
+        ["name", &stop.id],
+        ["depArrMacro", "dep"],
+        ["type", "stop"],
+        ["depType", "stopEvents"],
+        ["excludedModes", &excluded_modes],
+    ]
+    .map(|x| x.join("="))
+    .join("&");
+
+    let url = "https://transportnsw.info/api/trip/v1/departure-list-request?".to_string() + &params;
+
+    let data = fetch::<Value>(url).await?;
+
+    let stop_id = stop.id;
+    let stop_events = data["stopEvents"]
+        .as_array()
+        .ok_or(FetchError::Decode("missing stopEvents".to_string()))?;
+
+    // Skip anything that doesn't look like a departure rather than losing the lot
+    let departures = stop_events
+        .iter()
+        .filter_map(|stop| {
+            // Older responses only had the one time
+            let planned = stop["departureTimePlanned"]
+                .as_str()
+                .or(stop["departureTime"].as_str())?;
+            let location = &stop["location"];
+
+            Some(Departure {
+                stop_id: stop_id.clone(),
+                planned_time: DateTime::parse_from_rfc3339(planned).ok()?,
+                estimated_time: stop["departureTimeEstimated"]
+                    .as_str()
+                    .and_then(|estimated| DateTime::parse_from_rfc3339(estimated).ok()),
+                is_realtime: stop["isRealtimeControlled"].as_bool().unwrap_or(false),
+                number: stop["transportation"]["number"].as_str()?.to_string(),
+                mode: stop["transportation"]["product"]["class"]
+                    .as_u64()
+                    .and_then(mode_from_product_class),
+                destination: stop["transportation"]["destination"]["name"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+                stop_name: location["disassembledName"].as_str()?.to_string(),
+                platform: location["properties"]["platformName"]
+                    .as_str()
+                    .or(location["properties"]["platform"].as_str())
+                    .filter(|platform| !platform.is_empty())
+                    .map(str::to_string),
+                occupancy: stop["properties"]["occupancy"]
+                    .as_str()
+                    .or(location["properties"]["occupancy"].as_str())
+                    .and_then(Occupancy::parse),
+                is_cancelled: stop["isCancelled"].as_bool().unwrap_or(false),
+                is_scheduled: false,
+            })
+        })
+        .collect();
+
+    Ok(departures)
+}
+
+// https://transportnsw.info/api/trip/v1/add-info?filterPublicationStatus=current&itdLPxx_selStop=200060
+
+async fn fetch_alerts(
+    stop: BusStop,
+    now: DateTime<Local>,
+) -> Result<Vec<ServiceAlert>, FetchError> {
+    let params = [
+        ["filterPublicationStatus", "current"],
+        ["itdLPxx_selStop", &stop.id],
+    ]
+    .map(|x| x.join("="))
+    .join("&");
+
+    let url = "https://transportnsw.info/api/trip/v1/add-info?".to_string() + &params;
+
+    let data = fetch::<Value>(url).await?;
+
+    let infos = data["infos"]["current"]
+        .as_array()
+        .ok_or(FetchError::Decode("missing infos".to_string()))?;
+
+    let alerts = infos
+        .iter()
+        .filter(|info| {
+            // Anything without validity dates is taken as current, it was asked for that way
+            info["timestamps"]["validity"]
+                .as_array()
+                .is_none_or(|periods| {
+                    periods.is_empty()
+                        || periods.iter().any(|period| {
+                            let parse = |key: &str| {
+                                period[key]
+                                    .as_str()
+                                    .and_then(|time| DateTime::parse_from_rfc3339(time).ok())
+                            };
+                            parse("from").is_none_or(|from| from <= now)
+                                && parse("to").is_none_or(|to| now <= to)
+                        })
+                })
+        })
+        .filter_map(|info| {
+            Some(ServiceAlert {
+                id: info["id"].as_str()?.to_string(),
+                title: info["subtitle"].as_str()?.to_string(),
+                description: strip_tags(info["content"].as_str().unwrap_or_default()),
+                lines: info["affected"]["lines"]
+                    .as_array()
+                    .map(|lines| {
+                        lines
+                            .iter()
+                            .filter_map(|line| line["number"].as_str())
+                            .map(str::to_string)
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            })
+        })
+        .collect();
+
+    Ok(alerts)
+}
+
+// The content comes as HTML, only the words are wanted
+fn strip_tags(html: &str) -> String {
+    let mut text = String::new();
+    let mut in_tag = false;
+
+    for c in html.chars() {
+        match c {
+            '<' => {
+                in_tag = true;
+                text.push(' ');
+            }
+            '>' => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// https://transportnsw.info/api/trip/v1/stop-finder?name=Central&type=stop
+
+async fn search_stops(query: String) -> Result<Vec<BusStop>, FetchError> {
+    let params = [
+        ["name", &String::from(encode_uri_component(&query))],
+        ["type", "stop"],
+    ]
+    .map(|x| x.join("="))
+    .join("&");
+
+    let url = "https://transportnsw.info/api/trip/v1/stop-finder?".to_string() + &params;
+
+    let data = fetch::<Value>(url).await?;
+
+    let locations = data["locations"]
+        .as_array()
+        .ok_or(FetchError::Decode("missing locations".to_string()))?;
+
+    let stops = locations
+        .iter()
+        .filter(|location| matches!(location["type"].as_str(), Some("stop" | "platform")))
+        .filter_map(|location| {
+            // Start off with everything the stop is served by
+            let modes = location["modes"]
+                .as_array()
+                .map(|modes| {
+                    modes
+                        .iter()
+                        .filter_map(|mode| mode.as_u64())
+                        .filter_map(mode_from_product_class)
+                        .collect::<Vec<_>>()
+                })
+                .filter(|modes| !modes.is_empty())
+                .unwrap_or_else(default_modes);
+
+            Some(BusStop {
+                id: location["id"].as_str()?.to_string(),
+                name: location["name"].as_str()?.to_string(),
+                modes,
+                walking_minutes: 0,
+            })
+        })
+        .collect();
+
+    Ok(stops)
+}
@@ -0,0 +1,173 @@
+use std::rc::Rc;
+
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use gloo_console::log;
+use web_sys::UrlSearchParams;
+use yew::prelude::*;
+
+pub trait Clock {
+    fn now(&self) -> DateTime<Local>;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Local> {
+        Local::now()
+    }
+}
+
+// Starts at a made up time and runs from there, speed of 0 stops it dead
+pub struct FakeClock {
+    pub start: DateTime<Local>,
+    pub speed: f64,
+    started_at: DateTime<Local>,
+}
+
+impl FakeClock {
+    pub fn new(start: DateTime<Local>, speed: f64) -> Self {
+        Self {
+            start,
+            speed,
+            started_at: Local::now(),
+        }
+    }
+
+    // What it says when the real clock says `real_now`
+    fn at(&self, real_now: DateTime<Local>) -> DateTime<Local> {
+        let elapsed = (real_now - self.started_at).num_milliseconds() as f64;
+        self.start + chrono::Duration::milliseconds((elapsed * self.speed) as i64)
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> DateTime<Local> {
+        self.at(Local::now())
+    }
+}
+
+// Yew wants contexts to be comparable, a clock never changes once it's made
+#[derive(Clone)]
+pub struct ClockContext(Rc<dyn Clock>);
+
+impl ClockContext {
+    pub fn new(clock: impl Clock + 'static) -> Self {
+        Self(Rc::new(clock))
+    }
+
+    pub fn now(&self) -> DateTime<Local> {
+        self.0.now()
+    }
+}
+
+impl PartialEq for ClockContext {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+#[derive(Properties, Debug, PartialEq)]
+pub struct ClockProviderProps {
+    #[prop_or_default]
+    pub children: Html,
+}
+
+// ?now=2024-06-04T18:00 to pretend it's then, add &speed=60 to make a minute pass every second
+#[function_component]
+pub fn ClockProvider(props: &ClockProviderProps) -> Html {
+    let clock = use_memo((), |_| match fake_clock_from_url() {
+        Some(clock) => {
+            log!(format!("Using fake clock from {}", clock.start));
+            ClockContext::new(clock)
+        }
+        None => ClockContext::new(SystemClock),
+    });
+
+    html! {
+        <ContextProvider<ClockContext> context={(*clock).clone()}>
+            {props.children.clone()}
+        </ContextProvider<ClockContext>>
+    }
+}
+
+fn fake_clock_from_url() -> Option<FakeClock> {
+    let search = web_sys::window()?.location().search().ok()?;
+    let params = UrlSearchParams::new_with_str(&search).ok()?;
+
+    let start = parse_now(&params.get("now")?)?;
+    let speed = params
+        .get("speed")
+        .and_then(|speed| speed.parse::<f64>().ok())
+        .unwrap_or(1.0);
+
+    Some(FakeClock::new(start, speed))
+}
+
+fn parse_now(value: &str) -> Option<DateTime<Local>> {
+    let date_time = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M")
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S"))
+        .ok()?;
+
+    Local.from_local_datetime(&date_time).earliest()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    fn clock(speed: f64) -> (FakeClock, DateTime<Local>) {
+        let start = Local.with_ymd_and_hms(2024, 6, 4, 18, 0, 0).unwrap();
+        let clock = FakeClock::new(start, speed);
+        let started_at = clock.started_at;
+        (clock, started_at)
+    }
+
+    #[test]
+    fn starts_at_the_made_up_time() {
+        let (clock, started_at) = clock(1.0);
+
+        assert_eq!(clock.at(started_at), clock.start);
+    }
+
+    #[test]
+    fn runs_at_speed() {
+        let (clock, started_at) = clock(60.0);
+
+        assert_eq!(
+            clock.at(started_at + Duration::seconds(1)),
+            clock.start + Duration::minutes(1)
+        );
+        assert_eq!(
+            clock.at(started_at + Duration::minutes(1)),
+            clock.start + Duration::hours(1)
+        );
+    }
+
+    #[test]
+    fn runs_in_real_time_at_one() {
+        let (clock, started_at) = clock(1.0);
+
+        assert_eq!(
+            clock.at(started_at + Duration::minutes(90)),
+            clock.start + Duration::minutes(90)
+        );
+    }
+
+    #[test]
+    fn stands_still_at_zero() {
+        let (clock, started_at) = clock(0.0);
+
+        assert_eq!(clock.at(started_at + Duration::hours(5)), clock.start);
+    }
+
+    #[test]
+    fn parses_now_from_the_url() {
+        let expected = Local.with_ymd_and_hms(2024, 6, 4, 18, 0, 0).unwrap();
+
+        assert_eq!(parse_now("2024-06-04T18:00"), Some(expected));
+        assert_eq!(parse_now("2024-06-04T18:00:00"), Some(expected));
+        assert_eq!(parse_now("tomorrow"), None);
+    }
+}
@@ -1,16 +1,97 @@
-use std::rc::Rc;
+use std::{cell::RefCell, rc::Rc, time::Duration};
 
+use chrono::Utc;
+use futures::channel::oneshot;
 use gloo_console::log;
 use gloo_storage::{LocalStorage, Storage};
 use serde::Deserialize;
-use yew::{platform::spawn_local, prelude::*};
+use web_sys::{
+    js_sys::encode_uri_component,
+    wasm_bindgen::{closure::Closure, JsCast},
+    Position, PositionError, PositionOptions,
+};
+use yew::{
+    platform::{spawn_local, time::sleep},
+    prelude::*,
+};
 
-use super::super::utils::fetch;
+use super::{
+    super::utils::{fetch, with_timeout, FetchError},
+    status::{FetchAction, FetchStatus},
+};
+
+// The browser only starts its own timeout once the prompt is answered, nobody answers it on a kiosk
+const BROWSER_TIMEOUT_SECS: u64 = 30;
+
+// Backoff between lookups once they start failing
+const MIN_RETRY_SECS: u64 = 30;
+const MAX_RETRY_SECS: u64 = 15 * 60;
+
+pub const STORAGE_KEY: &str = "coordinates";
+const PLACES_KEY: &str = "places";
+
+// Each one is its own weather slide and forecast request
+pub const MAX_PLACES: usize = 4;
 
 // Easier to deal with a single 'variable'
 #[derive(Debug, PartialEq, Clone)]
 pub struct LocationCtx {
     pub coordinates: Coordinates,
+    // Something a person would recognise, when it's known
+    pub name: Option<String>,
+    pub source: LocationSource,
+    pub accuracy_meters: Option<f64>,
+    pub status: FetchStatus,
+    // Saved for a glance at the weather there, the primary one is whichever matches `coordinates`
+    pub places: Vec<Place>,
+}
+
+pub enum LocationAction {
+    Fetch(FetchAction<Location>),
+    // Already saved, this just gets the slides to pick them up
+    SetPlaces(Vec<Place>),
+}
+
+#[derive(Debug, PartialEq, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Place {
+    pub name: String,
+    pub coordinates: Coordinates,
+    // IANA name like "Australia/Sydney", open-meteo works it out when it's missing
+    #[serde(default)]
+    pub timezone: Option<String>,
+}
+
+impl Place {
+    pub fn location(&self) -> Location {
+        Location {
+            coordinates: self.coordinates.clone(),
+            name: Some(self.name.clone()),
+            source: LocationSource::Search,
+            accuracy_meters: None,
+        }
+    }
+}
+
+// Where the coordinates came from, best first
+#[derive(Debug, PartialEq, Clone, Copy, serde::Serialize, serde::Deserialize, Default)]
+pub enum LocationSource {
+    // Typed in, also what anything saved before this was known is taken to be
+    #[default]
+    Manual,
+    Search,
+    Browser,
+    IpLookup,
+}
+
+impl LocationSource {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            LocationSource::Manual => "entered by hand",
+            LocationSource::Search => "picked from search",
+            LocationSource::Browser => "this device's location",
+            LocationSource::IpLookup => "IP address lookup",
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone, serde::Serialize, serde::Deserialize, Default)]
@@ -19,22 +100,115 @@ pub struct Coordinates {
     pub longitude: f32,
 }
 
+// Saved under the same key plain coordinates used to be, the name is just extra
+#[derive(Debug, PartialEq, Clone, serde::Serialize, serde::Deserialize, Default)]
+pub struct Location {
+    #[serde(flatten)]
+    pub coordinates: Coordinates,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub source: LocationSource,
+    #[serde(default)]
+    pub accuracy_meters: Option<f64>,
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Default)]
 struct GeoLocationApiData {
     latitude: f32,
     longitude: f32,
+    #[serde(default)]
+    city: Option<String>,
 }
 
-impl Reducible for LocationCtx {
-    type Action = Coordinates;
+#[derive(Debug, Clone, PartialEq, Deserialize, Default)]
+struct GeocodingApiData {
+    #[serde(default)]
+    results: Vec<GeocodingResult>,
+}
 
-    fn reduce(self: Rc<Self>, data: Self::Action) -> Rc<Self> {
-        log!(format!("Reducing: {:?}", data));
-        LocationCtx {
+#[derive(Debug, Clone, PartialEq, Deserialize, Default)]
+pub struct GeocodingResult {
+    pub name: String,
+    pub latitude: f32,
+    pub longitude: f32,
+    #[serde(default)]
+    pub admin1: Option<String>,
+    #[serde(default)]
+    pub country: Option<String>,
+    #[serde(default)]
+    pub timezone: Option<String>,
+}
+
+impl GeocodingResult {
+    // e.g. "Manly, New South Wales, Australia"
+    pub fn display_name(&self) -> String {
+        [
+            Some(&self.name),
+            self.admin1.as_ref(),
+            self.country.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .join(", ")
+    }
+
+    pub fn location(&self) -> Location {
+        Location {
+            coordinates: Coordinates {
+                latitude: self.latitude,
+                longitude: self.longitude,
+            },
+            name: Some(self.display_name()),
+            source: LocationSource::Search,
+            accuracy_meters: None,
+        }
+    }
+
+    pub fn place(&self) -> Place {
+        Place {
+            name: self.name.clone(),
             coordinates: Coordinates {
-                longitude: data.longitude,
-                latitude: data.latitude,
+                latitude: self.latitude,
+                longitude: self.longitude,
             },
+            timezone: self.timezone.clone(),
+        }
+    }
+}
+
+impl Reducible for LocationCtx {
+    type Action = LocationAction;
+
+    fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
+        match action {
+            LocationAction::SetPlaces(places) => LocationCtx {
+                places,
+                ..(*self).clone()
+            },
+            LocationAction::Fetch(action) => {
+                log!(format!("Reducing: {:?}", action));
+                match action {
+                    FetchAction::Started => LocationCtx {
+                        status: self.status.started(),
+                        ..(*self).clone()
+                    },
+                    FetchAction::Finished(Ok(data)) => LocationCtx {
+                        coordinates: data.coordinates,
+                        name: data.name,
+                        source: data.source,
+                        accuracy_meters: data.accuracy_meters,
+                        status: self.status.succeeded(Utc::now()),
+                        ..(*self).clone()
+                    },
+                    FetchAction::Finished(Err(error)) => LocationCtx {
+                        status: self.status.failed(error),
+                        ..(*self).clone()
+                    },
+                }
+            }
         }
         .into()
     }
@@ -55,6 +229,11 @@ pub fn LocationProvider(props: &LocationProviderProps) -> Html {
             latitude: 0.0,
             longitude: 0.0,
         },
+        name: None,
+        source: LocationSource::default(),
+        accuracy_meters: None,
+        status: FetchStatus::default(),
+        places: load_places(),
     });
 
     let location_clone = location.clone();
@@ -64,26 +243,38 @@ pub fn LocationProvider(props: &LocationProviderProps) -> Html {
             return;
         }
 
-        let current_coordinates_result = LocalStorage::get::<Coordinates>("coordinates");
+        // Already on it
+        if location_clone.status.is_loading {
+            return;
+        }
 
-        if current_coordinates_result.is_ok() {
-            let data = current_coordinates_result.unwrap();
-            location_clone.dispatch(Coordinates {
-                latitude: data.latitude,
-                longitude: data.longitude,
-            });
+        if let Ok(data) = LocalStorage::get::<Location>(STORAGE_KEY) {
+            location_clone.dispatch(LocationAction::Fetch(FetchAction::Finished(Ok(data))));
         } else {
             spawn_local({
                 async move {
-                    let url = String::from("https://ipwho.is/");
-                    let data = fetch::<GeoLocationApiData>(url).await;
+                    location_clone.dispatch(LocationAction::Fetch(FetchAction::Started));
+
+                    // Failed last time, wait longer the longer it's been failing
+                    if let Some(since) = location_clone.status.failing_since {
+                        let failing_for = (Utc::now() - since).to_std().unwrap_or_default();
+                        sleep(failing_for.clamp(
+                            Duration::from_secs(MIN_RETRY_SECS),
+                            Duration::from_secs(MAX_RETRY_SECS),
+                        ))
+                        .await;
+                    }
 
-                    log!(format!("{:?}", data));
+                    // Saved, then the browser, then the IP address, and typing it in when all else fails
+                    let result = match browser_location().await {
+                        Ok(location) => Ok(location),
+                        Err(error) => {
+                            log!(format!("No browser location, trying IP lookup: {error}"));
+                            ip_location().await
+                        }
+                    };
 
-                    location_clone.dispatch(Coordinates {
-                        latitude: data.latitude,
-                        longitude: data.longitude,
-                    });
+                    location_clone.dispatch(LocationAction::Fetch(FetchAction::Finished(result)));
                 }
             });
         }
@@ -95,3 +286,117 @@ pub fn LocationProvider(props: &LocationProviderProps) -> Html {
         </ContextProvider<LocationContext>>
     }
 }
+
+pub fn load_places() -> Vec<Place> {
+    LocalStorage::get(PLACES_KEY).unwrap_or_default()
+}
+
+pub fn save_places(places: &[Place]) {
+    if let Err(error) = LocalStorage::set(PLACES_KEY, places) {
+        log!(format!("Could not save places: {error}"));
+    }
+}
+
+pub fn save_location(location: &Location) {
+    if let Err(error) = LocalStorage::set(STORAGE_KEY, location) {
+        log!(format!("Could not save location: {error}"));
+    }
+}
+
+// Asks for permission the first time, and gets turned down quietly after that if it was refused
+pub async fn browser_location() -> Result<Location, FetchError> {
+    let geolocation = web_sys::window()
+        .and_then(|window| window.navigator().geolocation().ok())
+        .ok_or(FetchError::Network("geolocation not supported".to_string()))?;
+
+    let (sender, receiver) = oneshot::channel::<Result<Position, String>>();
+    let sender = Rc::new(RefCell::new(Some(sender)));
+
+    let on_success = {
+        let sender = sender.clone();
+        Closure::once(move |position: Position| {
+            if let Some(sender) = sender.borrow_mut().take() {
+                let _ = sender.send(Ok(position));
+            }
+        })
+    };
+
+    let on_error = Closure::once(move |error: PositionError| {
+        if let Some(sender) = sender.borrow_mut().take() {
+            let _ = sender.send(Err(error.message()));
+        }
+    });
+
+    let options = PositionOptions::new();
+    options.set_timeout(BROWSER_TIMEOUT_SECS as u32 * 1000);
+    // Good enough if it's from the last hour
+    options.set_maximum_age(60 * 60 * 1000);
+
+    geolocation
+        .get_current_position_with_error_callback_and_options(
+            on_success.as_ref().unchecked_ref(),
+            Some(on_error.as_ref().unchecked_ref()),
+            &options,
+        )
+        .map_err(|error| FetchError::Network(format!("{error:?}")))?;
+
+    // The browser can still call back after the timeout below has given up on it
+    on_success.forget();
+    on_error.forget();
+
+    let position = with_timeout(
+        async {
+            match receiver.await {
+                Ok(Ok(position)) => Ok(position),
+                Ok(Err(message)) => Err(FetchError::Network(message)),
+                Err(_) => Err(FetchError::Network("geolocation went away".to_string())),
+            }
+        },
+        Duration::from_secs(BROWSER_TIMEOUT_SECS),
+    )
+    .await?;
+
+    let coords = position.coords();
+
+    Ok(Location {
+        coordinates: Coordinates {
+            latitude: coords.latitude() as f32,
+            longitude: coords.longitude() as f32,
+        },
+        name: None,
+        source: LocationSource::Browser,
+        accuracy_meters: Some(coords.accuracy()),
+    })
+}
+
+// Only as good as wherever the ISP says the connection is
+async fn ip_location() -> Result<Location, FetchError> {
+    let url = String::from("https://ipwho.is/");
+    fetch::<GeoLocationApiData>(url).await.map(|data| Location {
+        coordinates: Coordinates {
+            latitude: data.latitude,
+            longitude: data.longitude,
+        },
+        name: data.city,
+        source: LocationSource::IpLookup,
+        accuracy_meters: None,
+    })
+}
+
+// https://open-meteo.com/en/docs/geocoding-api
+
+pub async fn search_places(query: String) -> Result<Vec<GeocodingResult>, FetchError> {
+    let params = [
+        ["name", &String::from(encode_uri_component(&query))],
+        ["count", "10"],
+        ["language", "en"],
+        ["format", "json"],
+    ]
+    .map(|x| x.join("="))
+    .join("&");
+
+    let url = "https://geocoding-api.open-meteo.com/v1/search?".to_string() + &params;
+
+    // Nothing found comes back without any results at all
+    Ok(fetch::<GeocodingApiData>(url).await?.results)
+}
@@ -0,0 +1,61 @@
+use chrono::{DateTime, Utc};
+
+use crate::utils::FetchError;
+
+// What every provider goes through when it loads something
+#[derive(Debug, Clone, PartialEq)]
+pub enum FetchAction<T> {
+    Started,
+    Finished(Result<T, FetchError>),
+}
+
+impl<T> FetchAction<T> {
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> FetchAction<U> {
+        match self {
+            FetchAction::Started => FetchAction::Started,
+            FetchAction::Finished(result) => FetchAction::Finished(result.map(f)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FetchStatus {
+    pub is_loading: bool,
+    pub last_success: Option<DateTime<Utc>>,
+    pub error: Option<FetchError>,
+    // When things first started failing, not the latest attempt
+    pub failing_since: Option<DateTime<Utc>>,
+}
+
+impl FetchStatus {
+    pub fn started(&self) -> Self {
+        Self {
+            is_loading: true,
+            ..self.clone()
+        }
+    }
+
+    pub fn succeeded(&self, at: DateTime<Utc>) -> Self {
+        Self {
+            is_loading: false,
+            last_success: Some(at),
+            error: None,
+            failing_since: None,
+        }
+    }
+
+    pub fn failed(&self, error: FetchError) -> Self {
+        // Nothing's down when it just needs setting up
+        let failing_since = match error {
+            FetchError::Config(_) => None,
+            _ => self.failing_since.or(Some(Utc::now())),
+        };
+
+        Self {
+            is_loading: false,
+            last_success: self.last_success,
+            error: Some(error),
+            failing_since,
+        }
+    }
+}
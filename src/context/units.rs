@@ -0,0 +1,163 @@
+use std::rc::Rc;
+
+use gloo_console::log;
+use gloo_storage::{LocalStorage, Storage};
+use serde::{Deserialize, Serialize};
+use yew::prelude::*;
+
+const STORAGE_KEY: &str = "units";
+
+// Sent along with every forecast request, open-meteo does the converting
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
+pub struct Units {
+    pub temperature: TemperatureUnit,
+    pub wind_speed: WindSpeedUnit,
+    pub precipitation: PrecipitationUnit,
+}
+
+pub enum UnitsAction {
+    // Already saved, this just gets the weather to pick them up
+    Set(Units),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
+pub enum TemperatureUnit {
+    #[default]
+    Celsius,
+    Fahrenheit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
+pub enum WindSpeedUnit {
+    #[default]
+    KilometresPerHour,
+    MilesPerHour,
+    MetresPerSecond,
+    Knots,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
+pub enum PrecipitationUnit {
+    #[default]
+    Millimetres,
+    Inches,
+}
+
+impl Units {
+    pub fn load() -> Self {
+        LocalStorage::get(STORAGE_KEY).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Err(error) = LocalStorage::set(STORAGE_KEY, self) {
+            log!(format!("Could not save units: {error}"));
+        }
+    }
+
+    pub fn metric() -> Self {
+        Units::default()
+    }
+
+    pub fn imperial() -> Self {
+        Units {
+            temperature: TemperatureUnit::Fahrenheit,
+            wind_speed: WindSpeedUnit::MilesPerHour,
+            precipitation: PrecipitationUnit::Inches,
+        }
+    }
+}
+
+impl TemperatureUnit {
+    pub const ALL: [TemperatureUnit; 2] = [TemperatureUnit::Celsius, TemperatureUnit::Fahrenheit];
+
+    // What open-meteo calls it
+    pub fn param(&self) -> &'static str {
+        match self {
+            TemperatureUnit::Celsius => "celsius",
+            TemperatureUnit::Fahrenheit => "fahrenheit",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TemperatureUnit::Celsius => "ºC",
+            TemperatureUnit::Fahrenheit => "ºF",
+        }
+    }
+}
+
+impl WindSpeedUnit {
+    pub const ALL: [WindSpeedUnit; 4] = [
+        WindSpeedUnit::KilometresPerHour,
+        WindSpeedUnit::MilesPerHour,
+        WindSpeedUnit::MetresPerSecond,
+        WindSpeedUnit::Knots,
+    ];
+
+    pub fn param(&self) -> &'static str {
+        match self {
+            WindSpeedUnit::KilometresPerHour => "kmh",
+            WindSpeedUnit::MilesPerHour => "mph",
+            WindSpeedUnit::MetresPerSecond => "ms",
+            WindSpeedUnit::Knots => "kn",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            WindSpeedUnit::KilometresPerHour => "km/h",
+            WindSpeedUnit::MilesPerHour => "mph",
+            WindSpeedUnit::MetresPerSecond => "m/s",
+            WindSpeedUnit::Knots => "kn",
+        }
+    }
+}
+
+impl PrecipitationUnit {
+    pub const ALL: [PrecipitationUnit; 2] =
+        [PrecipitationUnit::Millimetres, PrecipitationUnit::Inches];
+
+    pub fn param(&self) -> &'static str {
+        match self {
+            PrecipitationUnit::Millimetres => "mm",
+            PrecipitationUnit::Inches => "inch",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PrecipitationUnit::Millimetres => "mm",
+            PrecipitationUnit::Inches => "in",
+        }
+    }
+}
+
+impl Reducible for Units {
+    type Action = UnitsAction;
+
+    fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
+        match action {
+            UnitsAction::Set(units) => units,
+        }
+        .into()
+    }
+}
+
+pub type UnitsContext = UseReducerHandle<Units>;
+
+#[derive(Properties, Debug, PartialEq)]
+pub struct UnitsProviderProps {
+    #[prop_or_default]
+    pub children: Html,
+}
+
+#[function_component]
+pub fn UnitsProvider(props: &UnitsProviderProps) -> Html {
+    let units = use_reducer(Units::load);
+
+    html! {
+        <ContextProvider<UnitsContext> context={units}>
+            {props.children.clone()}
+        </ContextProvider<UnitsContext>>
+    }
+}
@@ -1,29 +1,82 @@
 use std::rc::Rc;
 
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, NaiveDateTime};
 use gloo_console::log;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use yew::{platform::spawn_local, prelude::*};
 use yew_hooks::use_interval;
 
-use crate::context::location::LocationContext;
+use crate::{
+    cache::{fetch_cached, stale_while_revalidate, Cached},
+    context::{
+        location::LocationContext,
+        status::{FetchAction, FetchStatus},
+        units::{Units, UnitsContext},
+    },
+};
 
-use super::{super::utils::fetch, location::Coordinates};
+use super::location::{Coordinates, Place};
+
+const CACHE_KEY: &str = "weather";
 
 // Easier to deal with a single 'variable'
 #[derive(Debug, PartialEq, Clone)]
 pub struct WeatherCtx {
     pub is_loaded: bool,
     pub weather: WeatherData,
+    pub status: FetchStatus,
+    // Showing a cached copy older than the cache ttl
+    pub is_stale: bool,
+    // Different for every place, so there's something to tell them apart by
+    pub cache_key: String,
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Default)]
 pub struct WeatherData {
+    pub current: WeatherCurrent,
     pub daily: WeatherDaily,
     pub hourly: WeatherHourly,
     pub utc_offset_seconds: i32,
 }
 
-#[derive(Debug, Clone, PartialEq, Deserialize, Default)]
+impl WeatherData {
+    // Can be negative, and isn't always whole hours (India, Adelaide, ...)
+    pub fn offset(&self) -> FixedOffset {
+        FixedOffset::east_opt(self.utc_offset_seconds).unwrap_or(FixedOffset::east_opt(0).unwrap())
+    }
+}
+
+// Times come back as local time without an offset, days as just the date
+pub fn parse_time(time: &str, offset: FixedOffset) -> Option<DateTime<FixedOffset>> {
+    let naive = NaiveDateTime::parse_from_str(time, "%Y-%m-%dT%H:%M")
+        .or_else(|_| NaiveDate::parse_from_str(time, "%Y-%m-%d").map(|date| date.into()))
+        .ok()?;
+
+    naive.and_local_timezone(offset).single()
+}
+
+// What it's like outside right now, open-meteo averages it over the last 15 minutes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct WeatherCurrent {
+    pub time: String,
+    pub temperature_2m: f32,
+    pub apparent_temperature: f32,
+    pub relative_humidity_2m: i32,
+    pub wind_speed_10m: f32,
+    pub wind_direction_10m: i32,
+    pub wind_gusts_10m: f32,
+    pub weather_code: i32,
+    // 1 or 0 rather than a bool
+    pub is_day: i32,
+}
+
+impl WeatherCurrent {
+    pub fn is_day(&self) -> bool {
+        self.is_day == 1
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
 pub struct WeatherDaily {
     pub temperature_2m_max: Vec<f32>,
     pub temperature_2m_min: Vec<f32>,
@@ -33,30 +86,69 @@ pub struct WeatherDaily {
     pub weather_code: Vec<i32>,
     pub sunrise: Vec<String>,
     pub sunset: Vec<String>,
+    // These came later, anything cached before then won't have them
+    #[serde(default)]
+    pub apparent_temperature_max: Vec<f32>,
+    #[serde(default)]
+    pub apparent_temperature_min: Vec<f32>,
+    #[serde(default)]
+    pub relative_humidity_2m_mean: Vec<f32>,
+    #[serde(default)]
+    pub wind_speed_10m_max: Vec<f32>,
+    #[serde(default)]
+    pub wind_gusts_10m_max: Vec<f32>,
+    #[serde(default)]
+    pub wind_direction_10m_dominant: Vec<i32>,
 }
 
-#[derive(Debug, Clone, PartialEq, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
 pub struct WeatherHourly {
     pub temperature_2m: Vec<f32>,
     pub precipitation: Vec<f32>,
     pub time: Vec<String>,
     pub uv_index: Vec<f32>,
+    // Same as daily, might not be in the cache yet
+    #[serde(default)]
+    pub apparent_temperature: Vec<f32>,
+    #[serde(default)]
+    pub relative_humidity_2m: Vec<f32>,
+    #[serde(default)]
+    pub wind_speed_10m: Vec<f32>,
+    #[serde(default)]
+    pub wind_gusts_10m: Vec<f32>,
 }
 
-#[derive(Debug, Clone, PartialEq, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
 struct WeatherApiData {
+    // Missing from anything cached before it was asked for
+    #[serde(default)]
+    current: WeatherCurrent,
     daily: WeatherDaily,
     hourly: WeatherHourly,
     utc_offset_seconds: i32,
 }
 
 impl Reducible for WeatherCtx {
-    type Action = WeatherData;
+    type Action = FetchAction<Cached<WeatherData>>;
 
-    fn reduce(self: Rc<Self>, data: Self::Action) -> Rc<Self> {
-        WeatherCtx {
-            is_loaded: true,
-            weather: data,
+    fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
+        match action {
+            FetchAction::Started => WeatherCtx {
+                status: self.status.started(),
+                ..(*self).clone()
+            },
+            FetchAction::Finished(Ok(cached)) => WeatherCtx {
+                is_loaded: true,
+                weather: cached.value,
+                status: self.status.succeeded(cached.fetched_at),
+                is_stale: cached.is_stale,
+                ..(*self).clone()
+            },
+            // Keep showing whatever we had last
+            FetchAction::Finished(Err(error)) => WeatherCtx {
+                status: self.status.failed(error),
+                ..(*self).clone()
+            },
         }
         .into()
     }
@@ -68,39 +160,64 @@ pub type WeatherContext = UseReducerHandle<WeatherCtx>;
 pub struct WeatherProviderProps {
     #[prop_or_default]
     pub children: Html,
+    // Somewhere other than the main location
+    #[prop_or_default]
+    pub place: Option<Place>,
 }
 
 #[function_component]
 pub fn WeatherProvider(props: &WeatherProviderProps) -> Html {
-    let weather = use_reducer(|| WeatherCtx {
-        is_loaded: false,
-        weather: WeatherData {
-            ..Default::default()
-        },
-    });
-
     let location_ctx = use_context::<LocationContext>().unwrap();
+    let units = *use_context::<UnitsContext>().unwrap();
+
+    // Each place gets its own cache entry, otherwise they'd keep knocking each other out
+    let (coordinates, timezone, cache_key) = match &props.place {
+        Some(place) => (
+            place.coordinates.clone(),
+            place.timezone.clone(),
+            format!("{CACHE_KEY}_{}", place.name),
+        ),
+        None => (
+            location_ctx.coordinates.clone(),
+            None,
+            CACHE_KEY.to_string(),
+        ),
+    };
+
+    let weather = use_reducer({
+        let cache_key = cache_key.clone();
+        || WeatherCtx {
+            is_loaded: false,
+            weather: WeatherData {
+                ..Default::default()
+            },
+            status: FetchStatus::default(),
+            is_stale: false,
+            cache_key,
+        }
+    });
 
     let weather_clone = weather.clone();
-    use_effect_with(location_ctx.coordinates.clone(), move |coordinates| {
+    let timezone_clone = timezone.clone();
+    let cache_key_clone = cache_key.clone();
+    // The url has the units in it, so the cached copy won't get mixed up either
+    use_effect_with((coordinates.clone(), units), move |(coordinates, units)| {
         // Wait till we get data
         if coordinates.latitude == 0.0 {
             return;
         }
 
-        let coordinates_clone = coordinates.clone();
+        let url = weather_url(coordinates, timezone_clone.as_deref(), units);
         spawn_local(async move {
-            let data = fetch_weather(coordinates_clone).await;
-            weather_clone.dispatch(WeatherData {
-                daily: data.daily,
-                hourly: data.hourly,
-                utc_offset_seconds: data.utc_offset_seconds,
-            });
+            stale_while_revalidate(&cache_key_clone, url, cache_ttl(), |action| {
+                weather_clone.dispatch(action.map(into_weather_data));
+            })
+            .await;
         });
     });
 
     let update_every_millis = 1000 * 60 * 60;
-    let coordinates_clone1 = location_ctx.coordinates.clone();
+    let coordinates_clone1 = coordinates.clone();
     let weather_clone1 = weather.clone();
     use_interval(
         move || {
@@ -110,15 +227,13 @@ pub fn WeatherProvider(props: &WeatherProviderProps) -> Html {
                 return;
             }
 
-            let coordinates_clone2 = coordinates_clone1.clone();
+            let url = weather_url(&coordinates_clone1, timezone.as_deref(), &units);
             let weather_clone2 = weather_clone1.clone();
+            let cache_key = cache_key.clone();
             spawn_local(async move {
-                let data = fetch_weather(coordinates_clone2).await;
-                weather_clone2.dispatch(WeatherData {
-                    daily: data.daily,
-                    hourly: data.hourly,
-                    utc_offset_seconds: data.utc_offset_seconds,
-                });
+                weather_clone2.dispatch(FetchAction::Started);
+                let result = fetch_cached::<WeatherApiData>(&cache_key, url).await;
+                weather_clone2.dispatch(FetchAction::Finished(result.map(into_weather_data)));
             });
         },
         update_every_millis,
@@ -131,14 +246,40 @@ pub fn WeatherProvider(props: &WeatherProviderProps) -> Html {
     }
 }
 
-async fn fetch_weather(coordinates: Coordinates) -> WeatherApiData {
+fn weather_url(coordinates: &Coordinates, timezone: Option<&str>, units: &Units) -> String {
     let params = [
         ["latitude", &coordinates.latitude.to_string()],
         ["longitude", &coordinates.longitude.to_string()],
-        ["timezone", &"auto".to_string()],
+        ["timezone", timezone.unwrap_or("auto")],
+        ["temperature_unit", units.temperature.param()],
+        ["wind_speed_unit", units.wind_speed.param()],
+        ["precipitation_unit", units.precipitation.param()],
+        [
+            "current",
+            &[
+                "temperature_2m",
+                "apparent_temperature",
+                "relative_humidity_2m",
+                "wind_speed_10m",
+                "wind_direction_10m",
+                "wind_gusts_10m",
+                "weather_code",
+                "is_day",
+            ]
+            .join(","),
+        ],
         [
             "hourly",
-            &["temperature_2m", "precipitation", "uv_index"].join(","),
+            &[
+                "temperature_2m",
+                "precipitation",
+                "uv_index",
+                "apparent_temperature",
+                "relative_humidity_2m",
+                "wind_speed_10m",
+                "wind_gusts_10m",
+            ]
+            .join(","),
         ],
         [
             "daily",
@@ -150,6 +291,12 @@ async fn fetch_weather(coordinates: Coordinates) -> WeatherApiData {
                 "temperature_2m_min",
                 "precipitation_sum",
                 "precipitation_probability_max",
+                "apparent_temperature_max",
+                "apparent_temperature_min",
+                "relative_humidity_2m_mean",
+                "wind_speed_10m_max",
+                "wind_gusts_10m_max",
+                "wind_direction_10m_dominant",
             ]
             .join(","),
         ],
@@ -157,10 +304,85 @@ async fn fetch_weather(coordinates: Coordinates) -> WeatherApiData {
     .map(|x| x.join("="))
     .join("&");
 
-    let url = "https://api.open-meteo.com/v1/forecast?".to_string() + &params;
+    "https://api.open-meteo.com/v1/forecast?".to_string() + &params
+}
+
+// Refreshed hourly, anything older than that is stale
+fn cache_ttl() -> Duration {
+    Duration::hours(1)
+}
+
+fn into_weather_data(cached: Cached<WeatherApiData>) -> Cached<WeatherData> {
+    let data = cached.value;
+
+    Cached {
+        value: WeatherData {
+            current: data.current,
+            daily: data.daily,
+            hourly: data.hourly,
+            utc_offset_seconds: data.utc_offset_seconds,
+        },
+        fetched_at: cached.fetched_at,
+        is_stale: cached.is_stale,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Timelike};
+
+    use super::*;
+
+    fn offset(seconds: i32) -> FixedOffset {
+        WeatherData {
+            utc_offset_seconds: seconds,
+            ..Default::default()
+        }
+        .offset()
+    }
+
+    #[test]
+    fn takes_any_offset() {
+        // New York, India, Adelaide, Lord Howe Island and UTC
+        for seconds in [-18000, 19800, 34200, 37800, 0] {
+            assert_eq!(offset(seconds).local_minus_utc(), seconds);
+        }
+    }
+
+    #[test]
+    fn falls_back_to_utc() {
+        assert_eq!(offset(24 * 60 * 60).local_minus_utc(), 0);
+        assert_eq!(offset(-24 * 60 * 60).local_minus_utc(), 0);
+    }
 
-    let data = fetch::<WeatherApiData>(url).await;
-    log!(format!("{:?}", data));
+    #[test]
+    fn parses_times_in_the_places_offset() {
+        for seconds in [-18000, 19800, 34200, 37800, 0] {
+            let offset = offset(seconds);
+            let time = parse_time("2024-06-01T06:45", offset).unwrap();
 
-    return data;
+            assert_eq!(time, offset.with_ymd_and_hms(2024, 6, 1, 6, 45, 0).unwrap());
+            assert_eq!(time.offset().local_minus_utc(), seconds);
+        }
+    }
+
+    #[test]
+    fn parses_dates_as_midnight() {
+        let offset = offset(34200);
+        let date = parse_time("2024-06-01", offset).unwrap();
+
+        assert_eq!(
+            date.date_naive(),
+            NaiveDate::from_ymd_opt(2024, 6, 1).unwrap()
+        );
+        assert_eq!((date.hour(), date.minute()), (0, 0));
+        assert_eq!(date.offset().local_minus_utc(), 34200);
+    }
+
+    #[test]
+    fn rejects_anything_else() {
+        assert_eq!(parse_time("", offset(0)), None);
+        assert_eq!(parse_time("06:45", offset(0)), None);
+        assert_eq!(parse_time("2024-06-01T06:45:00Z", offset(0)), None);
+    }
 }
@@ -0,0 +1,127 @@
+use std::{cell::RefCell, rc::Rc};
+
+use futures::channel::oneshot;
+use serde::{de::DeserializeOwned, Serialize};
+use web_sys::{
+    wasm_bindgen::{closure::Closure, JsCast, JsValue},
+    IdbDatabase, IdbObjectStore, IdbRequest, IdbTransactionMode,
+};
+
+// Key-value storage for what's too big for LocalStorage's few megabytes,
+// values are kept as JSON same as everything else
+
+const DATABASE: &str = "bindicator";
+const STORE: &str = "store";
+const VERSION: u32 = 1;
+
+pub async fn get<T>(key: &str) -> Result<Option<T>, String>
+where
+    T: DeserializeOwned,
+{
+    let store = open_store(IdbTransactionMode::Readonly).await?;
+    let request = store.get(&JsValue::from_str(key)).map_err(describe)?;
+
+    match finish(&request).await?.as_string() {
+        Some(json) => serde_json::from_str(&json)
+            .map(Some)
+            .map_err(|error| error.to_string()),
+        None => Ok(None),
+    }
+}
+
+pub async fn set<T>(key: &str, value: &T) -> Result<(), String>
+where
+    T: Serialize,
+{
+    let json = serde_json::to_string(value).map_err(|error| error.to_string())?;
+
+    let store = open_store(IdbTransactionMode::Readwrite).await?;
+    let request = store
+        .put_with_key(&JsValue::from_str(&json), &JsValue::from_str(key))
+        .map_err(describe)?;
+
+    finish(&request).await.map(|_| ())
+}
+
+pub async fn delete(key: &str) -> Result<(), String> {
+    let store = open_store(IdbTransactionMode::Readwrite).await?;
+    let request = store.delete(&JsValue::from_str(key)).map_err(describe)?;
+
+    finish(&request).await.map(|_| ())
+}
+
+async fn open_store(mode: IdbTransactionMode) -> Result<IdbObjectStore, String> {
+    let factory = web_sys::window()
+        .and_then(|window| window.indexed_db().ok().flatten())
+        .ok_or("IndexedDB not supported".to_string())?;
+
+    let request = factory.open_with_u32(DATABASE, VERSION).map_err(describe)?;
+
+    // Only runs the first time, or after the version goes up
+    let on_upgrade = {
+        let request = request.clone();
+        Closure::<dyn FnMut()>::new(move || {
+            if let Ok(database) = request.result() {
+                let database: IdbDatabase = database.unchecked_into();
+                if !database.object_store_names().contains(STORE) {
+                    let _ = database.create_object_store(STORE);
+                }
+            }
+        })
+    };
+    request.set_onupgradeneeded(Some(on_upgrade.as_ref().unchecked_ref()));
+
+    let database: IdbDatabase = finish(&request).await?.unchecked_into();
+    request.set_onupgradeneeded(None);
+
+    database
+        .transaction_with_str_and_mode(STORE, mode)
+        .and_then(|transaction| transaction.object_store(STORE))
+        .map_err(describe)
+}
+
+// Waits for the request to succeed or fail, whichever comes first
+async fn finish(request: &IdbRequest) -> Result<JsValue, String> {
+    let (sender, receiver) = oneshot::channel::<Result<JsValue, String>>();
+    let sender = Rc::new(RefCell::new(Some(sender)));
+
+    let on_success = {
+        let sender = sender.clone();
+        let request = request.clone();
+        Closure::<dyn FnMut()>::new(move || {
+            if let Some(sender) = sender.borrow_mut().take() {
+                let _ = sender.send(request.result().map_err(describe));
+            }
+        })
+    };
+
+    let on_error = {
+        let request = request.clone();
+        Closure::<dyn FnMut()>::new(move || {
+            if let Some(sender) = sender.borrow_mut().take() {
+                let message = request
+                    .error()
+                    .ok()
+                    .flatten()
+                    .map(|error| error.message())
+                    .unwrap_or("IndexedDB request failed".to_string());
+                let _ = sender.send(Err(message));
+            }
+        })
+    };
+
+    request.set_onsuccess(Some(on_success.as_ref().unchecked_ref()));
+    request.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+
+    let result = receiver
+        .await
+        .unwrap_or(Err("IndexedDB went away".to_string()));
+
+    request.set_onsuccess(None);
+    request.set_onerror(None);
+    result
+}
+
+fn describe(error: JsValue) -> String {
+    format!("{error:?}")
+}
@@ -1,14 +1,28 @@
 mod components;
+use components::bin_calendar::BinCalendar;
+use components::bin_schedule_input::BinScheduleInput;
+use components::bus_stop_input::BusStopInput;
 use components::carousel::Carousel;
 use components::clock::ClockComponent;
+use components::departure_board::DepartureBoard;
 use components::dim::DimComponent;
 use components::location_input::LocationInput;
+use components::place_weather::PlaceSlides;
+use components::service_alerts::AlertBadge;
+use components::status::StatusBadge;
+use components::units_input::UnitsInput;
 use components::weather::WeatherComponent;
 use components::{bin::BinComponent, carousel::CarouselItem};
 
 mod context;
-use context::{bussin::BusProvider, location::LocationProvider, weather::WeatherProvider};
+use context::{
+    bussin::BusProvider, clock::ClockProvider, location::LocationProvider, units::UnitsProvider,
+    weather::WeatherProvider,
+};
 
+mod cache;
+mod idb;
+mod schedule;
 mod utils;
 
 use yew::{function_component, html, Html};
@@ -17,33 +31,56 @@ use yew::{function_component, html, Html};
 pub fn App() -> Html {
     html! {
         <div id="app" class="d-flex flex-column justify-content-between p-2" style="overflow: hidden;">
-            <DimComponent/>
-            <div class="d-flex justify-content-between">
-                <BinComponent/>
-                <ClockComponent/>
-            </div>
-            <LocationProvider>
+            <ClockProvider>
+                <DimComponent/>
+                <BusProvider>
+                    <div class="d-flex justify-content-between">
+                        <BinComponent/>
+                        <AlertBadge/>
+                        <ClockComponent/>
+                    </div>
+                    <LocationProvider>
+                        <UnitsProvider>
+                            <WeatherProvider>
 
-                <Carousel id="main">
+                                <Carousel id="main">
 
-                    <CarouselItem active={true}>
-                        <WeatherProvider>
-                            <WeatherComponent/>
-                        </WeatherProvider>
-                    </CarouselItem>
+                                    <CarouselItem active={true}>
+                                        <WeatherComponent/>
+                                    </CarouselItem>
 
-                    <CarouselItem active={false}>
-                        <LocationInput />
-                    </CarouselItem>
+                                    <PlaceSlides />
 
-                    <CarouselItem active={false}>
-                        <BusProvider>
-                        </BusProvider>
-                    </CarouselItem>
+                                    <CarouselItem active={false}>
+                                        <BinCalendar />
+                                    </CarouselItem>
 
-                </Carousel>
+                                    <CarouselItem active={false}>
+                                        <LocationInput />
+                                        <UnitsInput />
+                                    </CarouselItem>
 
-            </LocationProvider>
+                                    <CarouselItem active={false}>
+                                        <BinScheduleInput />
+                                    </CarouselItem>
+
+                                    <CarouselItem active={false}>
+                                        <DepartureBoard />
+                                    </CarouselItem>
+
+                                    <CarouselItem active={false}>
+                                        <BusStopInput />
+                                    </CarouselItem>
+
+                                </Carousel>
+
+                                <StatusBadge />
+
+                            </WeatherProvider>
+                        </UnitsProvider>
+                    </LocationProvider>
+                </BusProvider>
+            </ClockProvider>
         </div>
     }
 }
@@ -0,0 +1,348 @@
+use chrono::{Datelike, Days, NaiveDate, NaiveTime, Weekday};
+use gloo_console::log;
+use gloo_storage::{errors::StorageError, LocalStorage, Storage};
+use serde::{Deserialize, Serialize};
+
+use holidays::HolidayCalendar;
+use ics::CalendarEvent;
+
+pub mod holidays;
+pub mod ics;
+
+pub const STORAGE_KEY: &str = "bin_schedule";
+
+// How far ahead to look for the next collection, a year covers everything
+// except the really long `EveryNWeeks`
+const SEARCH_DAYS: u64 = 366;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Recurrence {
+    Weekly,
+    Fortnightly,
+    EveryNWeeks(u32),
+    // 1st..4th weekday of the month, 5 means the last one
+    MonthlyNthWeekday(u8),
+    // Imported from the council's calendar feed
+    Calendar(Vec<CalendarEvent>),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BinStream {
+    pub label: String,
+    pub color: String,
+    // First known collection, everything is counted from here
+    pub anchor: NaiveDate,
+    pub recurrence: Recurrence,
+    pub weekday: Weekday,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BinSchedule {
+    pub streams: Vec<BinStream>,
+    #[serde(default)]
+    pub holidays: HolidayCalendar,
+    #[serde(default)]
+    pub reminder: ReminderSettings,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReminderSettings {
+    // Bins go out this many days before the truck comes
+    pub days_before: u32,
+    pub from: NaiveTime,
+    // Stop nagging on the day once they've been picked up
+    pub until: NaiveTime,
+    pub chime: bool,
+}
+
+impl Default for ReminderSettings {
+    fn default() -> Self {
+        Self {
+            days_before: 1,
+            from: NaiveTime::from_hms_opt(17, 0, 0).unwrap(),
+            until: NaiveTime::from_hms_opt(7, 0, 0).unwrap(),
+            chime: false,
+        }
+    }
+}
+
+impl BinStream {
+    pub fn collects_on(&self, date: NaiveDate, holidays: &HolidayCalendar) -> bool {
+        // Council calendars already have the holiday changes baked in
+        if let Recurrence::Calendar(events) = &self.recurrence {
+            return date >= self.anchor && events.iter().any(|event| event.occurs_on(date));
+        }
+
+        // A holiday only ever pushes a collection back by a day
+        [date.pred_opt(), Some(date)]
+            .into_iter()
+            .flatten()
+            .any(|scheduled| self.scheduled_on(scheduled) && holidays.shift(scheduled) == date)
+    }
+
+    // Only there on `date` because a holiday pushed it back from the day before
+    pub fn is_shifted_on(&self, date: NaiveDate, holidays: &HolidayCalendar) -> bool {
+        date.pred_opt().is_some_and(|scheduled| {
+            self.scheduled_on(scheduled) && holidays.shift(scheduled) == date
+        })
+    }
+
+    fn scheduled_on(&self, date: NaiveDate) -> bool {
+        if date < self.anchor {
+            return false;
+        }
+
+        match &self.recurrence {
+            Recurrence::Calendar(_) => false,
+            _ if date.weekday() != self.weekday => false,
+            Recurrence::Weekly => true,
+            Recurrence::Fortnightly => self.weeks_since_anchor(date) % 2 == 0,
+            Recurrence::EveryNWeeks(n) => self.weeks_since_anchor(date) % (*n).max(1) as i64 == 0,
+            Recurrence::MonthlyNthWeekday(n) => {
+                nth_weekday_of_month(date) == *n || is_last(*n, date)
+            }
+        }
+    }
+
+    pub fn next_collection(
+        &self,
+        from: NaiveDate,
+        holidays: &HolidayCalendar,
+    ) -> Option<NaiveDate> {
+        // Expand the feed once rather than again for every day checked
+        if let Recurrence::Calendar(events) = &self.recurrence {
+            let from = from.max(self.anchor);
+            let end = from + Days::new(SEARCH_DAYS);
+            return events
+                .iter()
+                .flat_map(|event| event.occurrences_between(from, end))
+                .min();
+        }
+
+        from.iter_days()
+            .take(self.search_days() as usize)
+            .find(|date| self.collects_on(*date, holidays))
+    }
+
+    // A whole cycle, plus the day a holiday can push the last one back
+    fn search_days(&self) -> u64 {
+        match self.recurrence {
+            Recurrence::EveryNWeeks(n) => SEARCH_DAYS.max(u64::from(n) * 7 + 1),
+            _ => SEARCH_DAYS,
+        }
+    }
+
+    fn weeks_since_anchor(&self, date: NaiveDate) -> i64 {
+        let anchor_week = self.anchor.week(Weekday::Mon).first_day();
+        let date_week = date.week(Weekday::Mon).first_day();
+        (date_week - anchor_week).num_weeks()
+    }
+}
+
+fn nth_weekday_of_month(date: NaiveDate) -> u8 {
+    (date.day0() / 7 + 1) as u8
+}
+
+fn is_last(n: u8, date: NaiveDate) -> bool {
+    n == 5 && (date + Days::new(7)).month() != date.month()
+}
+
+impl BinSchedule {
+    pub fn load() -> Self {
+        match LocalStorage::get::<BinSchedule>(STORAGE_KEY) {
+            Ok(schedule) => schedule,
+            // Write the default out so the rota can be edited without recompiling
+            Err(StorageError::KeyNotFound(_)) => {
+                let schedule = BinSchedule::default();
+                schedule.save();
+                schedule
+            }
+            // Leave it be, it might just be from a newer version or a hand edit gone wrong
+            Err(error) => {
+                log!(format!(
+                    "Could not load bin schedule, using the default: {error}"
+                ));
+                BinSchedule::default()
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        if let Err(error) = LocalStorage::set(STORAGE_KEY, self) {
+            log!(format!("Could not save bin schedule: {error}"));
+        }
+    }
+
+    // Closest day any bin goes out, together with the streams collected that day
+    pub fn next_collection(&self, from: NaiveDate) -> Option<(NaiveDate, Vec<BinStream>)> {
+        let date = self
+            .streams
+            .iter()
+            .filter_map(|stream| stream.next_collection(from, &self.holidays))
+            .min()?;
+
+        Some((date, self.collected_on(date)))
+    }
+
+    // Calendar events get matched to streams by their summary, anything
+    // that doesn't look like an existing bin becomes a new one
+    pub fn import(&mut self, events: Vec<CalendarEvent>) {
+        let mut imported: Vec<BinStream> = Vec::new();
+
+        for event in events {
+            // Would match every bin and drag the whole feed into the first one
+            if event.summary.trim().is_empty() {
+                continue;
+            }
+
+            let existing = imported
+                .iter_mut()
+                .find(|stream| matches_label(&stream.label, &event.summary));
+
+            if let Some(stream) = existing {
+                stream.anchor = stream.anchor.min(event.start);
+                if let Recurrence::Calendar(events) = &mut stream.recurrence {
+                    events.push(event);
+                }
+                continue;
+            }
+
+            let color = self
+                .streams
+                .iter()
+                .find(|stream| matches_label(&stream.label, &event.summary))
+                .map(|stream| stream.color.clone())
+                .unwrap_or_else(|| guess_color(&event.summary).to_string());
+
+            imported.push(BinStream {
+                label: event.summary.clone(),
+                color,
+                anchor: event.start,
+                weekday: event.start.weekday(),
+                recurrence: Recurrence::Calendar(vec![event]),
+            });
+        }
+
+        self.streams = imported;
+    }
+
+    pub fn collected_on(&self, date: NaiveDate) -> Vec<BinStream> {
+        self.streams
+            .iter()
+            .filter(|stream| stream.collects_on(date, &self.holidays))
+            .cloned()
+            .collect()
+    }
+}
+
+fn matches_label(label: &str, summary: &str) -> bool {
+    let label = label.to_lowercase();
+    let summary = summary.to_lowercase();
+    summary.contains(&label) || label.contains(&summary)
+}
+
+// Councils are fairly consistent with what they call things
+fn guess_color(summary: &str) -> &'static str {
+    let summary = summary.to_lowercase();
+
+    if summary.contains("recycl") || summary.contains("yellow") {
+        "orange"
+    } else if summary.contains("garden") || summary.contains("organic") || summary.contains("green")
+    {
+        "green"
+    } else if summary.contains("waste") || summary.contains("rubbish") || summary.contains("red") {
+        "red"
+    } else {
+        "grey"
+    }
+}
+
+// The rota this thing was originally built for
+impl Default for BinSchedule {
+    fn default() -> Self {
+        let anchor = NaiveDate::from_ymd_opt(2024, 5, 13).unwrap();
+
+        Self {
+            streams: vec![
+                BinStream {
+                    label: "General waste".to_string(),
+                    color: "red".to_string(),
+                    anchor,
+                    recurrence: Recurrence::Weekly,
+                    weekday: Weekday::Mon,
+                },
+                BinStream {
+                    label: "Garden organics".to_string(),
+                    color: "green".to_string(),
+                    anchor,
+                    recurrence: Recurrence::Weekly,
+                    weekday: Weekday::Mon,
+                },
+                BinStream {
+                    label: "Recycling".to_string(),
+                    color: "orange".to_string(),
+                    anchor,
+                    recurrence: Recurrence::Fortnightly,
+                    weekday: Weekday::Mon,
+                },
+            ],
+            holidays: HolidayCalendar::default(),
+            reminder: ReminderSettings::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, month, day).unwrap()
+    }
+
+    fn thursdays() -> BinStream {
+        BinStream {
+            label: "Rubbish".to_string(),
+            color: "red".to_string(),
+            anchor: date(1, 4),
+            recurrence: Recurrence::Weekly,
+            weekday: Weekday::Thu,
+        }
+    }
+
+    #[test]
+    fn moves_to_friday_after_a_monday_holiday() {
+        let (stream, holidays) = (thursdays(), HolidayCalendar::default());
+
+        // King's Birthday was Monday the 10th of June
+        assert!(!stream.collects_on(date(6, 13), &holidays));
+        assert!(stream.collects_on(date(6, 14), &holidays));
+        assert!(stream.is_shifted_on(date(6, 14), &holidays));
+        assert_eq!(
+            stream.next_collection(date(6, 11), &holidays),
+            Some(date(6, 14))
+        );
+    }
+
+    #[test]
+    fn finds_collections_more_than_a_year_apart() {
+        let stream = BinStream {
+            recurrence: Recurrence::EveryNWeeks(60),
+            ..thursdays()
+        };
+
+        assert_eq!(
+            stream.next_collection(date(1, 6), &HolidayCalendar::default()),
+            Some(date(1, 4) + Days::new(60 * 7))
+        );
+    }
+
+    #[test]
+    fn stays_put_in_a_normal_week() {
+        let (stream, holidays) = (thursdays(), HolidayCalendar::default());
+
+        assert!(stream.collects_on(date(6, 20), &holidays));
+        assert!(!stream.is_shifted_on(date(6, 20), &holidays));
+        assert!(!stream.is_shifted_on(date(6, 21), &holidays));
+    }
+}
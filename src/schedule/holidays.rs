@@ -0,0 +1,242 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+use chrono::{Datelike, Days, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+
+type HolidayCache = HashMap<(Region, i32), Rc<HashSet<NaiveDate>>>;
+
+thread_local! {
+    // Every shift looks at up to a week of days, no point working Easter out again for each one
+    static HOLIDAYS: RefCell<HolidayCache> = RefCell::default();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Region {
+    Nsw,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HolidayCalendar {
+    pub region: Option<Region>,
+    // One-off days off the council announces, e.g. a state funeral
+    pub extra: Vec<NaiveDate>,
+}
+
+impl Default for HolidayCalendar {
+    fn default() -> Self {
+        Self {
+            region: Some(Region::Nsw),
+            extra: Vec::new(),
+        }
+    }
+}
+
+impl HolidayCalendar {
+    pub fn is_holiday(&self, date: NaiveDate) -> bool {
+        self.extra.contains(&date)
+            || self
+                .region
+                .is_some_and(|region| region.holidays_in(date.year()).contains(&date))
+    }
+
+    // Everything after a public holiday runs a day late for the rest of that week
+    pub fn shift(&self, collection: NaiveDate) -> NaiveDate {
+        let week_start = collection.week(Weekday::Mon).first_day();
+        let has_holiday = week_start
+            .iter_days()
+            .take_while(|date| *date <= collection)
+            .any(|date| self.is_holiday(date));
+
+        match has_holiday {
+            true => collection.succ_opt().unwrap_or(collection),
+            false => collection,
+        }
+    }
+}
+
+impl Region {
+    pub fn holidays(&self, year: i32) -> Vec<NaiveDate> {
+        match self {
+            Region::Nsw => nsw(year),
+        }
+    }
+
+    fn holidays_in(&self, year: i32) -> Rc<HashSet<NaiveDate>> {
+        HOLIDAYS.with_borrow_mut(|cache| {
+            cache
+                .entry((*self, year))
+                .or_insert_with(|| Rc::new(self.holidays(year).into_iter().collect()))
+                .clone()
+        })
+    }
+}
+
+fn nsw(year: i32) -> Vec<NaiveDate> {
+    let date = |month, day| NaiveDate::from_ymd_opt(year, month, day).unwrap();
+    let easter = easter_sunday(year);
+
+    let mut holidays = vec![
+        date(1, 1),
+        date(1, 26),
+        easter - Days::new(2),
+        easter - Days::new(1),
+        easter,
+        easter + Days::new(1),
+        // Anzac Day doesn't get a Monday when it lands on a weekend
+        date(4, 25),
+        nth_weekday(year, 6, Weekday::Mon, 2),
+        nth_weekday(year, 10, Weekday::Mon, 1),
+        date(12, 25),
+        date(12, 26),
+    ];
+
+    holidays.extend(observed_monday(date(1, 1)));
+    holidays.extend(observed_monday(date(1, 26)));
+
+    // Christmas and Boxing Day push each other along when either is on a weekend
+    match date(12, 25).weekday() {
+        Weekday::Sat => holidays.extend([date(12, 27), date(12, 28)]),
+        Weekday::Sun => holidays.push(date(12, 27)),
+        Weekday::Fri => holidays.push(date(12, 28)),
+        _ => {}
+    }
+
+    holidays
+}
+
+fn observed_monday(date: NaiveDate) -> Option<NaiveDate> {
+    match date.weekday() {
+        Weekday::Sat => Some(date + Days::new(2)),
+        Weekday::Sun => Some(date + Days::new(1)),
+        _ => None,
+    }
+}
+
+fn nth_weekday(year: i32, month: u32, weekday: Weekday, n: u8) -> NaiveDate {
+    NaiveDate::from_weekday_of_month_opt(year, month, weekday, n).unwrap()
+}
+
+// Anonymous Gregorian algorithm
+fn easter_sunday(year: i32) -> NaiveDate {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+
+    NaiveDate::from_ymd_opt(year, month as u32, day as u32).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn nsw() -> HolidayCalendar {
+        HolidayCalendar::default()
+    }
+
+    #[test]
+    fn finds_easter() {
+        assert_eq!(easter_sunday(2019), date(2019, 4, 21));
+        assert_eq!(easter_sunday(2024), date(2024, 3, 31));
+        assert_eq!(easter_sunday(2025), date(2025, 4, 20));
+        // As late as it gets
+        assert_eq!(easter_sunday(2038), date(2038, 4, 25));
+    }
+
+    #[test]
+    fn has_the_easter_long_weekend() {
+        for day in [29, 30, 31] {
+            assert!(nsw().is_holiday(date(2024, 3, day)));
+        }
+        assert!(nsw().is_holiday(date(2024, 4, 1)));
+        assert!(!nsw().is_holiday(date(2024, 4, 2)));
+    }
+
+    #[test]
+    fn has_the_monday_holidays() {
+        // King's Birthday and Labour Day
+        assert!(nsw().is_holiday(date(2024, 6, 10)));
+        assert!(nsw().is_holiday(date(2024, 10, 7)));
+    }
+
+    #[test]
+    fn moves_weekend_holidays_to_monday() {
+        // New Year's Day 2022 and Australia Day 2025 were on a weekend
+        assert!(nsw().is_holiday(date(2022, 1, 3)));
+        assert!(nsw().is_holiday(date(2025, 1, 27)));
+
+        // Australia Day 2024 was a Friday, nothing extra
+        assert!(nsw().is_holiday(date(2024, 1, 26)));
+        assert!(!nsw().is_holiday(date(2024, 1, 29)));
+    }
+
+    #[test]
+    fn leaves_anzac_day_on_the_weekend() {
+        assert!(nsw().is_holiday(date(2020, 4, 25)));
+        assert!(!nsw().is_holiday(date(2020, 4, 27)));
+    }
+
+    #[test]
+    fn moves_christmas_and_boxing_day_along() {
+        // Saturday and Sunday, both go to the next working days
+        assert!(nsw().is_holiday(date(2021, 12, 27)));
+        assert!(nsw().is_holiday(date(2021, 12, 28)));
+
+        // Sunday and Monday, only Christmas needs somewhere to go
+        assert!(nsw().is_holiday(date(2022, 12, 26)));
+        assert!(nsw().is_holiday(date(2022, 12, 27)));
+        assert!(!nsw().is_holiday(date(2022, 12, 28)));
+
+        // Friday and Saturday, Boxing Day goes to Monday
+        assert!(nsw().is_holiday(date(2020, 12, 28)));
+        assert!(!nsw().is_holiday(date(2020, 12, 29)));
+    }
+
+    #[test]
+    fn pushes_the_rest_of_the_week_back_a_day() {
+        // King's Birthday 2024 was Monday the 10th of June
+        assert_eq!(nsw().shift(date(2024, 6, 10)), date(2024, 6, 11));
+        assert_eq!(nsw().shift(date(2024, 6, 13)), date(2024, 6, 14));
+
+        // Neither the week before nor the week after
+        assert_eq!(nsw().shift(date(2024, 6, 7)), date(2024, 6, 7));
+        assert_eq!(nsw().shift(date(2024, 6, 17)), date(2024, 6, 17));
+    }
+
+    #[test]
+    fn only_pushes_days_after_the_holiday() {
+        // Anzac Day 2024 was a Thursday
+        assert_eq!(nsw().shift(date(2024, 4, 24)), date(2024, 4, 24));
+        assert_eq!(nsw().shift(date(2024, 4, 26)), date(2024, 4, 27));
+    }
+
+    #[test]
+    fn counts_extra_days_and_no_region() {
+        let calendar = HolidayCalendar {
+            region: None,
+            extra: vec![date(2024, 6, 12)],
+        };
+
+        assert!(!calendar.is_holiday(date(2024, 6, 10)));
+        assert_eq!(calendar.shift(date(2024, 6, 11)), date(2024, 6, 11));
+        assert_eq!(calendar.shift(date(2024, 6, 13)), date(2024, 6, 14));
+    }
+}
@@ -0,0 +1,488 @@
+use chrono::{Datelike, Days, Local, Months, NaiveDate, NaiveDateTime, TimeZone, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+
+// Just enough of RFC 5545 to read council collection calendars.
+// Everything is worked out on calendar dates, so a DST change between two
+// collections can't nudge one of them onto the wrong day.
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecurrenceRule {
+    pub frequency: Frequency,
+    pub interval: u32,
+    pub count: Option<u32>,
+    pub until: Option<NaiveDate>,
+    // (nth, weekday), nth of 0 means every one of them, negative counts from the end
+    pub by_day: Vec<(i32, Weekday)>,
+    pub by_month_day: Vec<i32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CalendarEvent {
+    pub summary: String,
+    pub start: NaiveDate,
+    pub rule: Option<RecurrenceRule>,
+    pub exceptions: Vec<NaiveDate>,
+}
+
+impl CalendarEvent {
+    pub fn occurs_on(&self, date: NaiveDate) -> bool {
+        date >= self.start && self.occurrences_between(date, date).contains(&date)
+    }
+
+    pub fn occurrences_between(&self, from: NaiveDate, end: NaiveDate) -> Vec<NaiveDate> {
+        let mut dates = match &self.rule {
+            Some(rule) => rule.expand(self.start, from, end),
+            None => vec![self.start],
+        };
+
+        dates.retain(|date| (from..=end).contains(date) && !self.exceptions.contains(date));
+        dates
+    }
+}
+
+impl RecurrenceRule {
+    // Dates from DTSTART up to `end`, skipping straight to the period `from` is in when nothing needs counting
+    fn expand(&self, start: NaiveDate, from: NaiveDate, end: NaiveDate) -> Vec<NaiveDate> {
+        let interval = self.interval.max(1);
+        let last = match self.until {
+            Some(until) => until.min(end),
+            None => end,
+        };
+
+        let mut dates = Vec::new();
+        // COUNT has to be counted from the start
+        let mut period = match self.count {
+            Some(_) => 0,
+            None => self.periods_between(start, from.max(start)) / interval,
+        };
+
+        while let Some(period_start) = self.period_start(start, period * interval) {
+            if period_start > last {
+                break;
+            }
+
+            let mut candidates = self.candidates(start, period_start);
+            candidates.sort();
+
+            for date in candidates {
+                if date < start || date > last {
+                    continue;
+                }
+                // COUNT includes occurrences that are later removed by EXDATE
+                if self.count.is_some_and(|count| dates.len() as u32 >= count) {
+                    return dates;
+                }
+                dates.push(date);
+            }
+
+            period += 1;
+        }
+
+        dates
+    }
+
+    // Whole periods (days, weeks, ...) between the one DTSTART is in and the one `date` is in
+    fn periods_between(&self, start: NaiveDate, date: NaiveDate) -> u32 {
+        let periods = match self.frequency {
+            Frequency::Daily => (date - start).num_days(),
+            Frequency::Weekly => (date.week(Weekday::Mon).first_day()
+                - start.week(Weekday::Mon).first_day())
+            .num_weeks(),
+            Frequency::Monthly => {
+                (date.year() - start.year()) as i64 * 12 + date.month() as i64
+                    - start.month() as i64
+            }
+            Frequency::Yearly => (date.year() - start.year()) as i64,
+        };
+
+        periods.clamp(0, u32::MAX as i64) as u32
+    }
+
+    fn period_start(&self, start: NaiveDate, offset: u32) -> Option<NaiveDate> {
+        match self.frequency {
+            Frequency::Daily => start.checked_add_days(Days::new(offset as u64)),
+            Frequency::Weekly => start
+                .week(Weekday::Mon)
+                .first_day()
+                .checked_add_days(Days::new(7 * offset as u64)),
+            Frequency::Monthly => start.with_day(1)?.checked_add_months(Months::new(offset)),
+            Frequency::Yearly => start
+                .with_day(1)?
+                .with_month(1)?
+                .checked_add_months(Months::new(12 * offset)),
+        }
+    }
+
+    fn candidates(&self, start: NaiveDate, period_start: NaiveDate) -> Vec<NaiveDate> {
+        match self.frequency {
+            Frequency::Daily => vec![period_start],
+            Frequency::Weekly => {
+                let weekdays = match self.by_day.is_empty() {
+                    true => vec![start.weekday()],
+                    false => self.by_day.iter().map(|(_, weekday)| *weekday).collect(),
+                };
+
+                weekdays
+                    .iter()
+                    .filter_map(|weekday| {
+                        period_start
+                            .checked_add_days(Days::new(weekday.num_days_from_monday() as u64))
+                    })
+                    .collect()
+            }
+            Frequency::Monthly => self.month_candidates(start, period_start),
+            Frequency::Yearly => period_start
+                .with_month(start.month())
+                .map(|month| self.month_candidates(start, month))
+                .unwrap_or_default(),
+        }
+    }
+
+    fn month_candidates(&self, start: NaiveDate, month_start: NaiveDate) -> Vec<NaiveDate> {
+        let mut dates = Vec::new();
+
+        for (nth, weekday) in &self.by_day {
+            let all = month_start
+                .iter_days()
+                .take_while(|date| date.month() == month_start.month())
+                .filter(|date| date.weekday() == *weekday)
+                .collect::<Vec<_>>();
+
+            match nth {
+                0 => dates.extend(all),
+                n if *n > 0 => dates.extend(all.get(*n as usize - 1)),
+                n => dates.extend(
+                    all.len()
+                        .checked_sub(n.unsigned_abs() as usize)
+                        .map(|i| all[i]),
+                ),
+            }
+        }
+
+        let days_in_month = month_start
+            .checked_add_months(Months::new(1))
+            .and_then(|next| next.pred_opt())
+            .map(|last| last.day() as i32)
+            .unwrap_or(28);
+
+        for day in &self.by_month_day {
+            let day = match *day < 0 {
+                true => days_in_month + day + 1,
+                false => *day,
+            };
+            dates.extend(month_start.with_day(day as u32));
+        }
+
+        if self.by_day.is_empty() && self.by_month_day.is_empty() {
+            dates.extend(month_start.with_day(start.day()));
+        }
+
+        dates
+    }
+}
+
+pub fn parse(input: &str) -> Vec<CalendarEvent> {
+    let mut events = Vec::new();
+    let mut current: Option<CalendarEvent> = None;
+    // How far inside the event we are, alarms have their own SUMMARY and such
+    let mut nested = 0;
+
+    for line in unfold(input) {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        // Parameters like TZID don't matter, we only ever look at the date part
+        let name = name.split(';').next().unwrap_or_default().to_uppercase();
+
+        match (name.as_str(), current.as_mut()) {
+            ("BEGIN", None) if value == "VEVENT" => {
+                current = Some(CalendarEvent {
+                    summary: String::new(),
+                    start: NaiveDate::MIN,
+                    rule: None,
+                    exceptions: Vec::new(),
+                });
+            }
+            ("BEGIN", Some(_)) => nested += 1,
+            ("END", Some(_)) if nested > 0 => nested -= 1,
+            (_, Some(_)) if nested > 0 => {}
+            ("END", Some(_)) if value == "VEVENT" => {
+                let event = current.take().unwrap();
+                if event.start != NaiveDate::MIN {
+                    events.push(event);
+                }
+            }
+            ("SUMMARY", Some(event)) => event.summary = unescape(value),
+            ("DTSTART", Some(event)) => {
+                if let Some(date) = parse_date(value) {
+                    event.start = date;
+                }
+            }
+            ("RRULE", Some(event)) => event.rule = parse_rule(value),
+            ("EXDATE", Some(event)) => event
+                .exceptions
+                .extend(value.split(',').filter_map(parse_date)),
+            _ => {}
+        }
+    }
+
+    events
+}
+
+// Long lines get folded onto the next one, starting with whitespace
+fn unfold(input: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+
+    for line in input.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.trim_end().to_string()),
+        }
+    }
+
+    lines
+}
+
+fn unescape(value: &str) -> String {
+    value
+        .replace("\\n", " ")
+        .replace("\\N", " ")
+        .replace("\\,", ",")
+        .replace("\\;", ";")
+        .replace("\\\\", "\\")
+}
+
+// 20240513, 20240513T060000 or 20240512T200000Z
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    let value = value.trim();
+
+    if let Some(utc) = value.strip_suffix('Z') {
+        let date_time = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some(
+            Utc.from_utc_datetime(&date_time)
+                .with_timezone(&Local)
+                .date_naive(),
+        );
+    }
+
+    NaiveDate::parse_from_str(value.get(0..8)?, "%Y%m%d").ok()
+}
+
+fn parse_rule(value: &str) -> Option<RecurrenceRule> {
+    let mut rule = RecurrenceRule {
+        frequency: Frequency::Weekly,
+        interval: 1,
+        count: None,
+        until: None,
+        by_day: Vec::new(),
+        by_month_day: Vec::new(),
+    };
+
+    // Some feeds leave a trailing `;` on the end
+    for part in value.split(';').filter(|part| !part.is_empty()) {
+        let (key, value) = part.split_once('=')?;
+
+        match key.to_uppercase().as_str() {
+            "FREQ" => {
+                rule.frequency = match value.to_uppercase().as_str() {
+                    "DAILY" => Frequency::Daily,
+                    "WEEKLY" => Frequency::Weekly,
+                    "MONTHLY" => Frequency::Monthly,
+                    "YEARLY" => Frequency::Yearly,
+                    _ => return None,
+                }
+            }
+            "INTERVAL" => rule.interval = value.parse().ok()?,
+            "COUNT" => rule.count = value.parse().ok(),
+            "UNTIL" => rule.until = parse_date(value),
+            "BYDAY" => rule.by_day = value.split(',').filter_map(parse_by_day).collect(),
+            "BYMONTHDAY" => {
+                rule.by_month_day = value
+                    .split(',')
+                    .filter_map(|day| day.parse().ok())
+                    .collect()
+            }
+            _ => {}
+        }
+    }
+
+    Some(rule)
+}
+
+// MO, 2MO, -1FR
+fn parse_by_day(value: &str) -> Option<(i32, Weekday)> {
+    // Last two characters, not bytes, a stray multibyte one would panic split_at
+    let split = value.char_indices().rev().nth(1)?.0;
+    let (nth, day) = (value.get(..split)?, value.get(split..)?);
+
+    let weekday = match day.to_uppercase().as_str() {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    };
+
+    let nth = match nth {
+        "" | "+" => 0,
+        nth => nth.trim_start_matches('+').parse().ok()?,
+    };
+
+    Some((nth, weekday))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn calendar(event: &str) -> String {
+        format!("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nSUMMARY:Recycling\r\n{event}\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n")
+    }
+
+    fn occurrences(event: &str, until: NaiveDate) -> Vec<NaiveDate> {
+        let events = parse(&calendar(event));
+        assert_eq!(events.len(), 1);
+        events[0].occurrences_between(NaiveDate::MIN, until)
+    }
+
+    #[test]
+    fn parses_by_day() {
+        assert_eq!(parse_by_day("MO"), Some((0, Weekday::Mon)));
+        assert_eq!(parse_by_day("2MO"), Some((2, Weekday::Mon)));
+        assert_eq!(parse_by_day("+2MO"), Some((2, Weekday::Mon)));
+        assert_eq!(parse_by_day("-1FR"), Some((-1, Weekday::Fri)));
+        assert_eq!(parse_by_day("XX"), None);
+        assert_eq!(parse_by_day("M"), None);
+        assert_eq!(parse_by_day(""), None);
+        assert_eq!(parse_by_day("aMO"), None);
+        // Would land split_at in the middle of a character
+        assert_eq!(parse_by_day("2MÖ"), None);
+        assert_eq!(parse_by_day("€"), None);
+    }
+
+    #[test]
+    fn ignores_a_trailing_semicolon() {
+        let dates = occurrences(
+            "DTSTART;VALUE=DATE:20240513\r\nRRULE:FREQ=WEEKLY;INTERVAL=2;COUNT=2;",
+            date(2024, 12, 31),
+        );
+
+        assert_eq!(dates, vec![date(2024, 5, 13), date(2024, 5, 27)]);
+    }
+
+    #[test]
+    fn keeps_the_event_summary_over_its_alarm() {
+        let events = parse(&calendar(
+            "DTSTART;VALUE=DATE:20240513\r\nBEGIN:VALARM\r\nACTION:DISPLAY\r\nSUMMARY:Put the bins out\r\nDTSTART:20240512\r\nEND:VALARM\r\nRRULE:FREQ=WEEKLY;COUNT=2",
+        ));
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].summary, "Recycling");
+        assert_eq!(events[0].start, date(2024, 5, 13));
+        assert_eq!(
+            events[0].occurrences_between(NaiveDate::MIN, date(2024, 12, 31)),
+            vec![date(2024, 5, 13), date(2024, 5, 20)]
+        );
+    }
+
+    #[test]
+    fn expands_interval() {
+        let dates = occurrences(
+            "DTSTART;VALUE=DATE:20240513\r\nRRULE:FREQ=WEEKLY;INTERVAL=2",
+            date(2024, 6, 30),
+        );
+
+        assert_eq!(
+            dates,
+            vec![
+                date(2024, 5, 13),
+                date(2024, 5, 27),
+                date(2024, 6, 10),
+                date(2024, 6, 24)
+            ]
+        );
+    }
+
+    #[test]
+    fn stops_at_until() {
+        let dates = occurrences(
+            "DTSTART;VALUE=DATE:20240513\r\nRRULE:FREQ=WEEKLY;UNTIL=20240527",
+            date(2024, 12, 31),
+        );
+
+        assert_eq!(
+            dates,
+            vec![date(2024, 5, 13), date(2024, 5, 20), date(2024, 5, 27)]
+        );
+    }
+
+    #[test]
+    fn stops_at_count() {
+        let dates = occurrences(
+            "DTSTART;VALUE=DATE:20240501\r\nRRULE:FREQ=MONTHLY;BYDAY=-1FR;COUNT=3",
+            date(2024, 12, 31),
+        );
+
+        assert_eq!(
+            dates,
+            vec![date(2024, 5, 31), date(2024, 6, 28), date(2024, 7, 26)]
+        );
+    }
+
+    #[test]
+    fn removes_exdates() {
+        let event = "DTSTART;VALUE=DATE:20240513\r\nRRULE:FREQ=WEEKLY;COUNT=4\r\nEXDATE;VALUE=DATE:20240520,20240603";
+        let dates = occurrences(event, date(2024, 12, 31));
+
+        // Still only four counted, the removed ones included
+        assert_eq!(dates, vec![date(2024, 5, 13), date(2024, 5, 27)]);
+
+        let events = parse(&calendar(event));
+        assert!(!events[0].occurs_on(date(2024, 5, 20)));
+        assert!(events[0].occurs_on(date(2024, 5, 27)));
+    }
+
+    #[test]
+    fn skipping_ahead_matches_expanding_from_the_start() {
+        let events = parse(&calendar(
+            "DTSTART;VALUE=DATE:20200106\r\nRRULE:FREQ=WEEKLY;INTERVAL=3;BYDAY=MO,TH",
+        ));
+        let event = &events[0];
+        let end = date(2025, 12, 31);
+        let all = event.occurrences_between(NaiveDate::MIN, end);
+
+        for day in date(2024, 1, 1).iter_days().take(60) {
+            assert_eq!(event.occurs_on(day), all.contains(&day), "{day}");
+        }
+    }
+
+    #[test]
+    fn keeps_the_day_across_dst() {
+        // Sydney goes back an hour on 7 April 2024 and forward on 6 October
+        let dates = occurrences(
+            "DTSTART;TZID=Australia/Sydney:20240331T060000\r\nRRULE:FREQ=WEEKLY;BYDAY=SU",
+            date(2024, 10, 13),
+        );
+
+        assert!(dates.contains(&date(2024, 3, 31)));
+        assert!(dates.contains(&date(2024, 4, 7)));
+        assert!(dates.contains(&date(2024, 10, 6)));
+        assert!(dates.iter().all(|date| date.weekday() == Weekday::Sun));
+        assert_eq!(dates.len(), 29);
+    }
+}
@@ -1,16 +1,189 @@
-use gloo_net::http::Request;
-use serde::de::DeserializeOwned;
+use std::{fmt, future::Future, pin::pin, time::Duration};
+
+use futures::future::{select, Either};
+use gloo_net::http::{Request, Response};
+use serde::{de::DeserializeOwned, Serialize};
+use yew::platform::time::sleep;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FetchError {
+    Network(String),
+    Status(u16),
+    Decode(String),
+    // Never made it out, trying again won't help
+    Request(String),
+    // Something needs setting up first, it's not that anything is down
+    Config(String),
+    Timeout,
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FetchError::Network(message) => write!(f, "network error: {message}"),
+            FetchError::Status(status) => write!(f, "server responded with {status}"),
+            FetchError::Decode(message) => write!(f, "unexpected response: {message}"),
+            FetchError::Request(message) => write!(f, "could not build request: {message}"),
+            FetchError::Config(message) => write!(f, "{message}"),
+            FetchError::Timeout => write!(f, "request timed out"),
+        }
+    }
+}
+
+impl FetchError {
+    // Worth another go, as opposed to the server telling us we're wrong
+    fn is_transient(&self) -> bool {
+        match self {
+            FetchError::Network(_) | FetchError::Timeout => true,
+            FetchError::Status(status) => *status == 429 || *status >= 500,
+            FetchError::Decode(_) | FetchError::Request(_) | FetchError::Config(_) => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FetchOptions {
+    pub timeout: Duration,
+    pub retries: u32,
+    // Doubles after every failed attempt
+    pub backoff: Duration,
+    // e.g. an API key some feeds want
+    pub headers: Vec<(String, String)>,
+}
+
+impl Default for FetchOptions {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(15),
+            retries: 3,
+            backoff: Duration::from_secs(2),
+            headers: Vec::new(),
+        }
+    }
+}
 
 // Pretty generic, can be extracted
-pub async fn fetch<T>(url: String) -> T
+pub async fn fetch<T>(url: String) -> Result<T, FetchError>
+where
+    T: DeserializeOwned,
+{
+    fetch_with(url, &FetchOptions::default()).await
+}
+
+pub async fn fetch_with<T>(url: String, options: &FetchOptions) -> Result<T, FetchError>
+where
+    T: DeserializeOwned,
+{
+    let body = fetch_text_with(url, options).await?;
+    serde_json::from_str(&body).map_err(|error| FetchError::Decode(error.to_string()))
+}
+
+// For APIs that take a query in the body, e.g. GraphQL
+pub async fn post_json<T>(url: String, body: &impl Serialize) -> Result<T, FetchError>
 where
     T: DeserializeOwned,
 {
-    return Request::get(&url)
-        .send()
-        .await
-        .unwrap()
-        .json::<T>()
-        .await
-        .unwrap();
+    let options = FetchOptions::default();
+    let body =
+        serde_json::to_string(body).map_err(|error| FetchError::Request(error.to_string()))?;
+
+    let text = with_retries(&options, || async {
+        let request = Request::post(&url)
+            .header("Content-Type", "application/json")
+            .body(body.clone())
+            .map_err(|error| FetchError::Request(error.to_string()))?;
+
+        checked(request.send().await)?
+            .text()
+            .await
+            .map_err(|error| FetchError::Network(error.to_string()))
+    })
+    .await?;
+
+    serde_json::from_str(&text).map_err(|error| FetchError::Decode(error.to_string()))
+}
+
+pub async fn fetch_text(url: String) -> Result<String, FetchError> {
+    fetch_text_with(url, &FetchOptions::default()).await
+}
+
+pub async fn fetch_text_with(url: String, options: &FetchOptions) -> Result<String, FetchError> {
+    with_retries(options, || async {
+        get(&url, options)
+            .await?
+            .text()
+            .await
+            .map_err(|error| FetchError::Network(error.to_string()))
+    })
+    .await
+}
+
+// For binary feeds like GTFS-Realtime
+pub async fn fetch_bytes_with(url: String, options: &FetchOptions) -> Result<Vec<u8>, FetchError> {
+    with_retries(options, || async {
+        get(&url, options)
+            .await?
+            .binary()
+            .await
+            .map_err(|error| FetchError::Network(error.to_string()))
+    })
+    .await
+}
+
+async fn with_retries<T, F, Fut>(options: &FetchOptions, attempt: F) -> Result<T, FetchError>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<T, FetchError>>,
+{
+    let mut delay = options.backoff;
+    let mut retry = 0;
+
+    loop {
+        let error = match with_timeout(attempt(), options.timeout).await {
+            Ok(body) => return Ok(body),
+            Err(error) => error,
+        };
+
+        if retry >= options.retries || !error.is_transient() {
+            return Err(error);
+        }
+
+        sleep(delay).await;
+        delay *= 2;
+        retry += 1;
+    }
+}
+
+pub async fn with_timeout<T>(
+    request: impl Future<Output = Result<T, FetchError>>,
+    timeout: Duration,
+) -> Result<T, FetchError> {
+    let request = pin!(request);
+    let timer = pin!(sleep(timeout));
+
+    match select(request, timer).await {
+        Either::Left((result, _)) => result,
+        Either::Right(_) => Err(FetchError::Timeout),
+    }
+}
+
+async fn get(url: &str, options: &FetchOptions) -> Result<Response, FetchError> {
+    let request = options
+        .headers
+        .iter()
+        .fold(Request::get(url), |request, (name, value)| {
+            request.header(name, value)
+        });
+
+    checked(request.send().await)
+}
+
+fn checked(response: Result<Response, gloo_net::Error>) -> Result<Response, FetchError> {
+    let response = response.map_err(|error| FetchError::Network(error.to_string()))?;
+
+    if !response.ok() {
+        return Err(FetchError::Status(response.status()));
+    }
+
+    Ok(response)
 }